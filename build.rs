@@ -0,0 +1,39 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(dbgonly_active)");
+    println!("cargo:rustc-check-cfg=cfg(dbgonly_disable)");
+    println!("cargo:rustc-check-cfg=cfg(dbgonly_force_disable)");
+    println!("cargo:rustc-check-cfg=cfg(dbgonly_forced)");
+    println!("cargo:rustc-check-cfg=cfg(dbgonly_sanitizer)");
+    println!("cargo:rerun-if-env-changed=CARGO_ENCODED_RUSTFLAGS");
+
+    // The macros print when `debug_assertions` are on, unless the features
+    // say otherwise, and never while fuzzing. `force-disable` wins over
+    // `force-enable`.
+    let debug_assertions = env::var_os("CARGO_CFG_DEBUG_ASSERTIONS").is_some();
+    let fuzzing = env::var_os("CARGO_CFG_FUZZING").is_some();
+    let force_enable = env::var_os("CARGO_FEATURE_FORCE_ENABLE").is_some();
+    let force_disable = env::var_os("CARGO_FEATURE_FORCE_DISABLE").is_some();
+    if (debug_assertions || force_enable) && !force_disable && !fuzzing {
+        println!("cargo:rustc-cfg=dbgonly_active");
+        if force_enable {
+            println!("cargo:rustc-cfg=dbgonly_forced");
+        }
+    }
+    // `dbgtest!` does not follow `debug_assertions`, but still follows
+    // `force-disable`.
+    if force_disable {
+        println!("cargo:rustc-cfg=dbgonly_force_disable");
+    }
+
+    // There is no stable `cfg` for sanitizers, so look for the flag that
+    // enables them instead.
+    let rustflags = env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+    if rustflags
+        .split('\x1f')
+        .any(|flag| flag.starts_with("-Zsanitizer") || flag.starts_with("sanitizer="))
+    {
+        println!("cargo:rustc-cfg=dbgonly_sanitizer");
+    }
+}
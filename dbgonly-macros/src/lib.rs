@@ -0,0 +1,189 @@
+//! The `#[trace]` attribute of [dbgonly](https://docs.rs/dbgonly), which is
+//! re-exported there with the `trace` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, FnArg, Ident, ItemFn, Pat, ReturnType, Type};
+
+/// Prints the arguments of a function when it is called and its return value
+/// when it returns. This version of the attribute will print nothing and
+/// leave the function as written in release builds.
+///
+/// Every argument that is bound to a name is printed as a field, so their
+/// types have to implement `Debug`, as does the return type. Calls that are
+/// made while the function runs on the same thread are indented:
+///
+/// ```rust
+/// #[dbgonly::trace]
+/// fn fib(n: u32) -> u32 {
+///     if n < 2 {
+///         n
+///     } else {
+///         fib(n - 1) + fib(n - 2)
+///     }
+/// }
+///
+/// assert_eq!(fib(2), 1);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:1] -> fib n=2
+///     [src/main.rs:1] -> fib n=1
+///     [src/main.rs:1] <- fib = 1
+///     [src/main.rs:1] -> fib n=0
+///     [src/main.rs:1] <- fib = 0
+/// [src/main.rs:1] <- fib = 1
+/// ```
+///
+/// Arguments that do not implement `Debug`, or are too noisy to print, can
+/// be left out with `skip`:
+///
+/// ```rust
+/// # struct Connection;
+/// #[dbgonly::trace(skip(conn))]
+/// fn query(conn: &mut Connection, id: u64) -> Option<String> {
+///     # let _ = conn;
+///     Some(format!("user {}", id))
+/// }
+/// # assert!(query(&mut Connection, 1).is_some());
+/// ```
+///
+/// `self` is never printed. Async functions print their exit when the
+/// future completes, but their calls are not indented, since they may
+/// resume on another thread. If the function panics, no exit is printed.
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[proc_macro_attribute]
+pub fn trace(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut skip: Vec<Ident> = Vec::new();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("skip") {
+            meta.parse_nested_meta(|arg| {
+                skip.push(arg.path.require_ident()?.clone());
+                Ok(())
+            })
+        } else {
+            Err(meta.error("expected `skip(...)`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+    let item = parse_macro_input!(item as ItemFn);
+    expand(item, &skip)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(item: ItemFn, skip: &[Ident]) -> syn::Result<TokenStream2> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = &item;
+    if let Some(constness) = &sig.constness {
+        return Err(syn::Error::new_spanned(
+            constness,
+            "`#[trace]` cannot be used on a `const fn`",
+        ));
+    }
+
+    // Mixed-site hygiene keeps these from shadowing arguments of the same
+    // name in the body.
+    let trace = Ident::new("trace", Span::mixed_site());
+    let ret = Ident::new("ret", Span::mixed_site());
+    let name = sig.ident.to_string();
+    let enter = format!("-> {}", name);
+    let exit = format!("<- {}", name);
+    let args = sig.inputs.iter().filter_map(|input| match input {
+        FnArg::Typed(arg) => match &*arg.pat {
+            Pat::Ident(pat) if !skip.contains(&pat.ident) => {
+                let ident = &pat.ident;
+                let key = ident.to_string();
+                Some(quote!((#key, &#ident as &dyn ::core::fmt::Debug)))
+            }
+            _ => None,
+        },
+        FnArg::Receiver(_) => None,
+    });
+
+    // The type is spelled out where possible so that `?` in the body knows
+    // what to convert errors into.
+    let ty = match &sig.output {
+        ReturnType::Type(_, ty) if !contains_impl(ty) => Some(ty),
+        _ => None,
+    };
+    let run = match (&sig.asyncness, ty) {
+        (Some(_), Some(ty)) => quote! {
+            async move {
+                let #ret: #ty = #block;
+                #ret
+            }
+            .await
+        },
+        (Some(_), None) => quote!(async move #block.await),
+        (None, Some(ty)) => quote!((move || -> #ty #block)()),
+        (None, None) => quote!((move || #block)()),
+    };
+    let value = match &sig.output {
+        ReturnType::Default => quote!(::core::option::Option::None),
+        ReturnType::Type(..) => quote!(::core::option::Option::Some(&#ret)),
+    };
+    let indent = sig.asyncness.is_none();
+
+    Ok(quote! {
+        ::dbgonly::__trace! {
+            {
+                #(#attrs)*
+                #vis #sig {
+                    let #trace = ::dbgonly::__private::Trace::enter(
+                        {
+                            static CALLSITE: ::dbgonly::Callsite = ::dbgonly::Callsite::new(
+                                file!(),
+                                line!(),
+                                module_path!(),
+                                ::core::option::Option::Some(#enter),
+                            )
+                            .silenced_if(::dbgonly::__silenced!());
+                            &CALLSITE
+                        },
+                        {
+                            static CALLSITE: ::dbgonly::Callsite = ::dbgonly::Callsite::new(
+                                file!(),
+                                line!(),
+                                module_path!(),
+                                ::core::option::Option::Some(#exit),
+                            )
+                            .silenced_if(::dbgonly::__silenced!());
+                            &CALLSITE
+                        },
+                        &[#(#args),*],
+                        #indent,
+                    );
+                    #[allow(clippy::redundant_closure_call)]
+                    let #ret = #run;
+                    #trace.exit(#value);
+                    #ret
+                }
+            }
+            {
+                #item
+            }
+        }
+    })
+}
+
+/// Returns whether a type contains an `impl Trait`, which cannot be written
+/// in a closure's return type.
+fn contains_impl(ty: &Type) -> bool {
+    fn walk(tokens: TokenStream2) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == "impl",
+            TokenTree::Group(group) => walk(group.stream()),
+            _ => false,
+        })
+    }
+    walk(ty.to_token_stream())
+}
@@ -0,0 +1,67 @@
+//! Output without the standard library.
+
+use core::fmt;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+static WRITER: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Sets the function that the `dbgonly` macros write to when the crate is
+/// built without the `std` feature.
+///
+/// The function is called with one rendered record at a time, in the same
+/// `[src/main.rs:2] a * 2 = 4` form as on stderr and without a trailing
+/// newline. Until a writer is set, records are discarded. On an embedded
+/// target, it typically writes to a serial port or a semihosting console:
+///
+/// ```rust,ignore
+/// fn write(record: core::fmt::Arguments<'_>) {
+///     let _ = writeln!(SERIAL.lock(), "{}", record);
+/// }
+///
+/// dbgonly::set_core_writer(write);
+/// ```
+///
+/// With the `defmt` feature, records are logged with `defmt::debug!` instead
+/// and the writer is not used.
+pub fn set_core_writer(writer: fn(fmt::Arguments<'_>)) {
+    WRITER.store(writer as *mut (), Ordering::Release);
+}
+
+#[doc(hidden)]
+pub fn emit(record: &Record<'_>) {
+    let writer = WRITER.load(Ordering::Acquire);
+    if writer.is_null() {
+        return;
+    }
+    // SAFETY: Only `fn(fmt::Arguments<'_>)` pointers are ever stored.
+    let writer: fn(fmt::Arguments<'_>) = unsafe { core::mem::transmute(writer) };
+    writer(format_args!("{}", record));
+}
+
+/// A record of one of the `dbgonly` macros without the standard library,
+/// which renders like [`Record`](https://docs.rs/dbgonly/*/dbgonly/struct.Record.html)
+/// does with the default settings.
+#[doc(hidden)]
+pub struct Record<'a> {
+    pub file: &'static str,
+    pub line: u32,
+    pub expr: Option<&'a str>,
+    pub value: Option<fmt::Arguments<'a>>,
+    pub fields: &'a [(&'static str, &'a dyn fmt::Debug)],
+}
+
+impl fmt::Display for Record<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}:{}]", self.file, self.line)?;
+        match (self.expr, self.value) {
+            (Some(expr), Some(value)) => write!(f, " {} = {}", expr, value)?,
+            (Some(expr), None) => write!(f, " {}", expr)?,
+            (None, Some(value)) => write!(f, " {}", value)?,
+            (None, None) => {}
+        }
+        for (key, value) in self.fields {
+            write!(f, " {}={:?}", key, value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! A viewer for the records sent by `dbgonly::sink::Udp`.
+//!
+//! ```text
+//! dbgonly-tail [--listen ADDR] [--file TEXT] [--tag TEXT] [--no-color]
+//! ```
+//!
+//! Records are only shown if their file contains the `--file` text and their
+//! context contains the `--tag` text. While running, enter `p` to pause or
+//! resume the stream (records received while paused are shown on resume) and
+//! `q` to quit.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::net::UdpSocket;
+use std::process;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:7777";
+const MAX_PAUSED: usize = 10_000;
+
+#[derive(Debug)]
+struct Options {
+    listen: String,
+    file: Option<String>,
+    tag: Option<String>,
+    color: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    paused: bool,
+    backlog: VecDeque<String>,
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|message| {
+        eprintln!("dbgonly-tail: {}", message);
+        eprintln!("usage: dbgonly-tail [--listen ADDR] [--file TEXT] [--tag TEXT] [--no-color]");
+        process::exit(2);
+    });
+    let socket = UdpSocket::bind(&options.listen).unwrap_or_else(|err| {
+        eprintln!("dbgonly-tail: cannot listen on {}: {}", options.listen, err);
+        process::exit(1);
+    });
+    eprintln!(
+        "dbgonly-tail: listening on {} (p + enter: pause/resume, q + enter: quit)",
+        options.listen
+    );
+
+    let state = Arc::new(Mutex::new(State::default()));
+    let input_state = Arc::clone(&state);
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            match line.trim() {
+                "p" => {
+                    let mut state = input_state.lock().unwrap_or_else(PoisonError::into_inner);
+                    state.paused = !state.paused;
+                    if state.paused {
+                        eprintln!("dbgonly-tail: paused");
+                    } else {
+                        eprintln!("dbgonly-tail: resumed ({} buffered)", state.backlog.len());
+                        let mut stdout = io::stdout().lock();
+                        for record in state.backlog.drain(..) {
+                            let _ = stdout.write_all(record.as_bytes());
+                        }
+                    }
+                }
+                "q" => process::exit(0),
+                _ => {}
+            }
+        }
+    });
+
+    let mut buf = vec![0; 65536];
+    loop {
+        let len = match socket.recv(&mut buf) {
+            Ok(len) => len,
+            Err(err) => {
+                eprintln!("dbgonly-tail: {}", err);
+                continue;
+            }
+        };
+        let datagram = String::from_utf8_lossy(&buf[..len]);
+        let Some((header, text)) = datagram.split_once('\n') else {
+            continue;
+        };
+        let mut header = header.split('\t');
+        let file = header.next().unwrap_or_default();
+        let context = header.nth(2).unwrap_or_default();
+        if !matches(&options.file, file) || !matches(&options.tag, context) {
+            continue;
+        }
+        let record = render(text, options.color);
+        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.paused {
+            if state.backlog.len() == MAX_PAUSED {
+                state.backlog.pop_front();
+            }
+            state.backlog.push_back(record);
+        } else {
+            let _ = io::stdout().lock().write_all(record.as_bytes());
+        }
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        listen: DEFAULT_LISTEN.to_owned(),
+        file: None,
+        tag: None,
+        color: io::stdout().is_terminal(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--listen" => options.listen = value("--listen")?,
+            "--file" => options.file = Some(value("--file")?),
+            "--tag" => options.tag = Some(value("--tag")?),
+            "--no-color" => options.color = false,
+            _ => return Err(format!("unknown argument `{}`", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn matches(filter: &Option<String>, text: &str) -> bool {
+    filter
+        .as_ref()
+        .is_none_or(|filter| text.contains(filter.as_str()))
+}
+
+/// Renders a record on its own line, highlighting the `[file:line]` prefix
+/// and the expression.
+fn render(text: &str, color: bool) -> String {
+    if !color {
+        return format!("{}\n", text);
+    }
+    let Some(end) = text.find(']') else {
+        return format!("{}\n", text);
+    };
+    let (prefix, rest) = text.split_at(end + 1);
+    match rest.split_once(" = ") {
+        Some((expr, value)) => format!(
+            "\x1b[2m{}\x1b[0m\x1b[1m{}\x1b[0m = {}\n",
+            prefix, expr, value
+        ),
+        None => format!("\x1b[2m{}\x1b[0m{}\n", prefix, rest),
+    }
+}
@@ -0,0 +1,294 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::directive::Directives;
+
+const UNREGISTERED: u8 = 0;
+const ENABLED: u8 = 1;
+const DISABLED: u8 = 2;
+/// Disabled for good, without being registered.
+const SILENCED: u8 = 3;
+
+type Filter = Arc<dyn Fn(&Callsite) -> bool + Send + Sync>;
+
+/// The registered callsites and the filters that were applied to them.
+///
+/// The filters are never called with the lock held, since they are user code
+/// that may reach callsites of its own. Instead, they are cloned out of the
+/// registry, and their answers are only stored if the filters have not been
+/// replaced in the meantime.
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    callsites: Vec::new(),
+    filter: None,
+    modules: None,
+    generation: 0,
+});
+
+struct Registry {
+    callsites: Vec<&'static Callsite>,
+    filter: Option<Filter>,
+    /// The module filter, read from the environment when the first callsite
+    /// is registered unless it was set before.
+    modules: Option<Arc<Directives>>,
+    /// Incremented whenever a filter is replaced.
+    generation: u64,
+}
+
+thread_local! {
+    /// Whether the current thread is running the callsite filter.
+    static IN_FILTER: Cell<bool> = const { Cell::new(false) };
+}
+
+type Key = (&'static str, u32, &'static str, Option<&'static str>);
+
+/// Callsites whose location is only known at runtime, created on first use
+/// and kept for reuse.
+static INTERNED: Mutex<BTreeMap<Key, &'static Callsite>> = Mutex::new(BTreeMap::new());
+
+/// The static description of a single macro invocation.
+///
+/// Every invocation of a `dbgonly` macro owns one `Callsite` in a `static`.
+/// It is registered the first time it is reached, and from then on whether
+/// it prints is decided by a single relaxed atomic load, so callsites that
+/// are disabled with [`set_callsite_filter`] cost next to nothing even in hot
+/// loops.
+pub struct Callsite {
+    file: &'static str,
+    line: u32,
+    module_path: &'static str,
+    expr: Option<&'static str>,
+    state: AtomicU8,
+}
+
+impl Callsite {
+    #[doc(hidden)]
+    pub const fn new(
+        file: &'static str,
+        line: u32,
+        module_path: &'static str,
+        expr: Option<&'static str>,
+    ) -> Self {
+        Callsite {
+            file,
+            line,
+            module_path,
+            expr,
+            state: AtomicU8::new(UNREGISTERED),
+        }
+    }
+
+    /// Silences the callsite if `silenced` is set, which the macros do when
+    /// the crate that invokes them is silenced with
+    /// [`__silenced!`](crate::__silenced).
+    #[doc(hidden)]
+    pub const fn silenced_if(mut self, silenced: bool) -> Self {
+        if silenced {
+            self.state = AtomicU8::new(SILENCED);
+        }
+        self
+    }
+
+    /// The source file of the macro invocation.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// The line of the macro invocation.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The module path of the macro invocation.
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+
+    /// The source code of the expression, or `None` for `dbgonly!()`.
+    pub fn expr(&self) -> Option<&'static str> {
+        self.expr
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn is_enabled(&'static self) -> bool {
+        if !crate::config::enabled() {
+            return false;
+        }
+        match self.state.load(Ordering::Relaxed) {
+            ENABLED => true,
+            DISABLED | SILENCED => false,
+            _ => self.register(),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn register(&'static self) -> bool {
+        // Callsites reached by the filter itself print nothing, and are
+        // registered once they are reached outside of it.
+        if IN_FILTER.with(Cell::get) {
+            return false;
+        }
+        loop {
+            let (filter, modules, generation) = {
+                let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+                // Another thread may have registered the callsite while this
+                // one was waiting for the lock.
+                if self.state.load(Ordering::Relaxed) != UNREGISTERED {
+                    return self.state.load(Ordering::Relaxed) == ENABLED;
+                }
+                registry.snapshot()
+            };
+            let enabled = self.decide(filter.as_ref(), &modules);
+            let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+            if self.state.load(Ordering::Relaxed) != UNREGISTERED {
+                return self.state.load(Ordering::Relaxed) == ENABLED;
+            }
+            if registry.generation == generation {
+                registry.callsites.push(self);
+                self.set_enabled(enabled);
+                return enabled;
+            }
+        }
+    }
+
+    fn decide(&self, filter: Option<&Filter>, modules: &Directives) -> bool {
+        modules.allows(self.module_path)
+            && filter.is_none_or(|filter| {
+                let outer = IN_FILTER.with(|in_filter| in_filter.replace(true));
+                let enabled = filter(self);
+                IN_FILTER.with(|in_filter| in_filter.set(outer));
+                enabled
+            })
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        let state = if enabled { ENABLED } else { DISABLED };
+        self.state.store(state, Ordering::Relaxed);
+    }
+}
+
+/// Returns the callsite for a location that is only known at runtime,
+/// creating it the first time.
+pub fn callsite_at(
+    file: &'static str,
+    line: u32,
+    module_path: &'static str,
+    expr: Option<&'static str>,
+) -> &'static Callsite {
+    let mut interned = INTERNED.lock().unwrap_or_else(PoisonError::into_inner);
+    interned
+        .entry((file, line, module_path, expr))
+        .or_insert_with(|| Box::leak(Box::new(Callsite::new(file, line, module_path, expr))))
+}
+
+impl fmt::Debug for Callsite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Callsite")
+            .field("file", &self.file)
+            .field("line", &self.line)
+            .field("module_path", &self.module_path)
+            .field("expr", &self.expr)
+            .finish()
+    }
+}
+
+/// Decides at runtime which callsites print.
+///
+/// The filter is called once for every callsite that has already been
+/// reached and once for every callsite the first time it is reached; its
+/// answer is cached until the filter is replaced. Callsites for which it
+/// returns `false` still evaluate and return their value, but print nothing:
+///
+/// ```rust
+/// dbgonly::set_callsite_filter(|callsite| !callsite.module_path().contains("::physics"));
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// # dbgonly::reset_callsite_filter();
+/// ```
+///
+/// Callsites also have to pass the [module filter](set_module_filter).
+///
+/// The filter may use the macros of this crate itself, but the callsites it
+/// reaches print nothing while it runs:
+///
+/// ```rust
+/// dbgonly::set_callsite_filter(|callsite| dbgonly::dbgonly!(callsite.line()) > 0);
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// # dbgonly::reset_callsite_filter();
+/// ```
+pub fn set_callsite_filter(filter: impl Fn(&Callsite) -> bool + Send + Sync + 'static) {
+    replace_filter(Some(Arc::new(filter)));
+}
+
+/// Removes the filter set with [`set_callsite_filter`], enabling all
+/// callsites that pass the [module filter](set_module_filter) again.
+pub fn reset_callsite_filter() {
+    replace_filter(None);
+}
+
+/// Decides at runtime which modules print, using the same syntax as the
+/// `DBGONLY` environment variable.
+///
+/// The filter is a comma-separated list of directives. A module path enables
+/// that module and the ones below it, `path=off` disables them, and a bare
+/// `on` or `off` sets the default for the modules that are not listed. The
+/// most specific path wins. Unless a default is given, listing any module
+/// disables all others, so the output can be narrowed down without
+/// recompiling:
+///
+/// ```sh
+/// DBGONLY=myapp::parser,myapp::parser::lexer=off cargo run
+/// ```
+///
+/// Until this is called, the filter is read from `DBGONLY` when the first
+/// callsite is reached. Invalid directives are reported on stderr and
+/// ignored.
+///
+/// ```rust
+/// dbgonly::set_module_filter("off,myapp::net");
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// //      ^-- prints nothing outside of `myapp::net`
+/// # dbgonly::set_module_filter("on");
+/// ```
+pub fn set_module_filter(spec: &str) {
+    let directives = Arc::new(Directives::parse(spec));
+    reapply(|registry| registry.modules = Some(directives));
+}
+
+fn replace_filter(filter: Option<Filter>) {
+    reapply(|registry| registry.filter = filter);
+}
+
+/// Changes the filters and applies them to the registered callsites.
+fn reapply(change: impl FnOnce(&mut Registry)) {
+    let (callsites, (filter, modules, generation)) = {
+        let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+        change(&mut registry);
+        registry.generation += 1;
+        (registry.callsites.clone(), registry.snapshot())
+    };
+    let enabled: Vec<bool> = callsites
+        .iter()
+        .map(|callsite| callsite.decide(filter.as_ref(), &modules))
+        .collect();
+    let registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    // Otherwise, the filters were replaced again and the newer ones apply.
+    if registry.generation == generation {
+        for (callsite, enabled) in callsites.iter().zip(enabled) {
+            callsite.set_enabled(enabled);
+        }
+    }
+}
+
+impl Registry {
+    /// Clones the current filters out of the registry.
+    fn snapshot(&mut self) -> (Option<Filter>, Arc<Directives>, u64) {
+        let modules = self
+            .modules
+            .get_or_insert_with(|| Arc::new(Directives::from_env()));
+        (self.filter.clone(), Arc::clone(modules), self.generation)
+    }
+}
@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::Record;
+
+thread_local! {
+    /// The output of the open captures of the current thread, innermost last.
+    static CAPTURES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Captures the output of the current thread until the returned guard is
+/// dropped.
+///
+/// While the guard is alive, every record emitted by the current thread is
+/// rendered as it would be on stderr and kept in memory instead of being
+/// handed to the [sink](crate::sink). This lets tests assert on what was
+/// printed, and keeps the output of passing tests out of `cargo test`, which
+/// does not capture writes to stderr made outside of `print!` and friends:
+///
+/// ```rust
+/// let capture = dbgonly::capture();
+/// let total = dbgonly::dbgonly!(2 + 3);
+/// //          ^-- prints nothing
+/// assert_eq!(total, 5);
+/// let output = capture.output();
+/// //  ^-- "[src/main.rs:2] 2 + 3 = 5\n"
+/// # let _ = output;
+/// ```
+///
+/// The captured output is discarded when the guard is dropped. Captures
+/// nest, and only the innermost one receives records. Records emitted by
+/// other threads are not captured.
+pub fn capture() -> Capture {
+    let index = CAPTURES.with(|captures| {
+        let mut captures = captures.borrow_mut();
+        captures.push(String::new());
+        captures.len() - 1
+    });
+    Capture {
+        index,
+        _not_send: PhantomData,
+    }
+}
+
+/// The guard returned by [`capture`].
+pub struct Capture {
+    index: usize,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Capture {
+    /// Returns everything captured so far, one record per line.
+    pub fn output(&self) -> String {
+        CAPTURES.with(|captures| {
+            captures
+                .borrow()
+                .get(self.index)
+                .cloned()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Returns everything captured so far and starts over with an empty
+    /// buffer.
+    pub fn take(&self) -> String {
+        CAPTURES.with(|captures| {
+            captures
+                .borrow_mut()
+                .get_mut(self.index)
+                .map(std::mem::take)
+                .unwrap_or_default()
+        })
+    }
+}
+
+impl fmt::Debug for Capture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Capture")
+            .field("output", &self.output())
+            .finish()
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        // Also closes captures that were opened later but not dropped yet,
+        // such as ones that were leaked.
+        CAPTURES.with(|captures| captures.borrow_mut().truncate(self.index));
+    }
+}
+
+/// Appends the record to the innermost capture of the current thread, and
+/// returns whether there was one.
+pub(crate) fn capture_record(record: &Record<'_>) -> bool {
+    let capturing = capturing();
+    if capturing {
+        // Rendered before borrowing the buffer, since rendering runs `Debug`
+        // implementations that may emit records of their own.
+        let line = format!("{}\n", record);
+        CAPTURES.with(|captures| {
+            if let Some(output) = captures.borrow_mut().last_mut() {
+                output.push_str(&line);
+            }
+        });
+    }
+    capturing
+}
+
+/// Returns whether the current thread has an open capture.
+pub(crate) fn capturing() -> bool {
+    CAPTURES
+        .try_with(|captures| !captures.borrow().is_empty())
+        .unwrap_or(false)
+}
@@ -0,0 +1,872 @@
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+
+use crate::{context, watch};
+
+/// Checks a condition and prints a message if it does not hold, without
+/// panicking. This version of the macro will check nothing and be optimized
+/// out in release builds.
+///
+/// This is like `debug_assert!` for cases where the program should keep
+/// running after the violation, so that what happens next can be watched. An
+/// optional value that helps understand the violation is printed along with
+/// the condition; like the message of `debug_assert!`, it is only evaluated
+/// when the condition does not hold:
+///
+/// ```rust
+/// use dbgonly::dbg_invariant;
+/// let queue = vec![1, 2, 3];
+/// dbg_invariant!(queue.len() < 3, queue);
+/// //  ^-- prints: [src/main.rs:3] invariant `queue.len() < 3` violated: queue = [1, 2, 3]
+/// dbg_invariant!(queue.iter().all(|&n| n > 0));
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_invariant {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::__emit!(
+                ::core::option::Option::Some(concat!(
+                    "invariant `",
+                    stringify!($cond),
+                    "` violated"
+                )),
+                ::core::option::Option::None,
+                &[]
+            )
+        }
+    };
+    ($cond:expr, $val:expr $(,)?) => {
+        if !$cond {
+            $crate::__emit!(
+                ::core::option::Option::Some(concat!(
+                    "invariant `",
+                    stringify!($cond),
+                    "` violated: ",
+                    stringify!($val)
+                )),
+                ::core::option::Option::Some(format_args!("{:#?}", &$val)),
+                &[]
+            )
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_invariant {
+    ($cond:expr $(,)?) => {
+        if false {
+            let _: bool = $cond;
+        }
+    };
+    ($cond:expr, $val:expr $(,)?) => {
+        if false {
+            let _: bool = $cond;
+            let _ = &$val;
+        }
+    };
+}
+
+/// Returns the value of a given expression and prints it only if it falls
+/// outside the expected range. This version of the macro will print nothing
+/// and be optimized out in release builds.
+///
+/// Any range expression works, including half-open ranges such as `0..` and
+/// `..=max`. This makes it possible to watch for the one bad sample among
+/// millions of good ones:
+///
+/// ```rust
+/// use dbgonly::dbg_range;
+/// for sample in [0.2, 0.7, 1.3] {
+///     let gain = dbg_range!(sample * 0.9, 0.0..=1.0);
+///     //         ^-- prints: [src/main.rs:3] sample * 0.9 = 1.1700000000000002 (outside 0.0..=1.0)
+///     # let _ = gain;
+/// }
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_range {
+    ($val:expr, $range:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if !::core::ops::RangeBounds::contains(&$range, &tmp) {
+                    $crate::__emit!(
+                        ::core::option::Option::Some(stringify!($val)),
+                        ::core::option::Option::Some(format_args!(
+                            "{:#?} (outside {})",
+                            &tmp,
+                            stringify!($range)
+                        )),
+                        &[]
+                    );
+                }
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_range {
+    ($val:expr, $range:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _ = ::core::ops::RangeBounds::contains(&$range, &tmp);
+                }
+                tmp
+            }
+        }
+    };
+}
+
+/// Returns the value of a given floating-point expression and prints it the
+/// first time it is NaN or infinite at this call site. This version of the
+/// macro will print nothing and be optimized out in release builds.
+///
+/// Every call site reports only once, so the report points at the first
+/// place a NaN or infinity showed up in a loop rather than burying it under
+/// its consequences. If backtraces are enabled with `RUST_BACKTRACE=1`, one
+/// is printed along with the value:
+///
+/// ```rust
+/// use dbgonly::dbg_finite;
+/// let weights = [0.5_f64, 0.0, 2.0];
+/// for w in weights {
+///     let inv = dbg_finite!(1.0 / w);
+///     //        ^-- prints once: [src/main.rs:4] 1.0 / w = inf (non-finite, first at this call site)
+///     # let _ = inv;
+/// }
+/// ```
+///
+/// The expression can be an `f32` or `f64`, or a reference to one. To flag
+/// non-finite values printed by the other macros, see
+/// [`set_flag_non_finite`](crate::set_flag_non_finite).
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_finite {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                static REPORTED: ::core::sync::atomic::AtomicBool =
+                    ::core::sync::atomic::AtomicBool::new(false);
+                if !$crate::__private::Float::is_finite(&tmp)
+                    && !REPORTED.swap(true, ::core::sync::atomic::Ordering::Relaxed)
+                {
+                    $crate::__emit!(
+                        ::core::option::Option::Some(stringify!($val)),
+                        ::core::option::Option::Some(format_args!(
+                            "{:?} (non-finite, first at this call site){}",
+                            &tmp, $crate::__private::backtrace())),
+                        &[]);
+                }
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_finite!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_finite {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_finite!($val)),+,)
+    };
+}
+
+/// Floating-point types accepted by [`dbg_finite!`](crate::dbg_finite).
+pub trait Float {
+    fn is_finite(&self) -> bool;
+}
+
+impl Float for f32 {
+    fn is_finite(&self) -> bool {
+        f32::is_finite(*self)
+    }
+}
+
+impl Float for f64 {
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+}
+
+impl<T: Float + ?Sized> Float for &T {
+    fn is_finite(&self) -> bool {
+        T::is_finite(self)
+    }
+}
+
+impl<T: Float + ?Sized> Float for &mut T {
+    fn is_finite(&self) -> bool {
+        T::is_finite(self)
+    }
+}
+
+/// Unwraps an `Option` or `Result` like `expect`, with the expression and the
+/// debugging state in the panic message. In release builds, this is a plain
+/// `expect`.
+///
+/// A panic message that only says what went wrong often means running the
+/// program again just to find out the values involved. In debug builds, the
+/// message also contains the source of the expression, the current
+/// [`context`](crate::context) and the latest values stored with
+/// [`dbg_set!`](crate::dbg_set):
+///
+/// ```rust,should_panic
+/// use dbgonly::{dbg_expect, dbg_set};
+/// let config = "port = 80a";
+/// dbg_set!("config", config);
+/// let port: u16 = dbg_expect!(config[7..].parse(), "invalid port");
+/// # let _ = port;
+/// ```
+///
+/// This panics with:
+///
+/// ```text,ignore
+/// invalid port: ParseIntError { kind: InvalidDigit }
+/// [dbgonly] expression: `config[7..].parse()`
+/// [dbgonly] watches (1):
+/// [src/main.rs:3] config = "port = 80a"
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_expect {
+    ($val:expr, $msg:expr $(,)?) => {
+        match $crate::__private::Expect::into_result($val) {
+            ::core::result::Result::Ok(tmp) => tmp,
+            ::core::result::Result::Err(err) => {
+                $crate::__private::expect_failed($msg, err, stringify!($val))
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_expect {
+    ($val:expr, $msg:expr $(,)?) => {
+        $crate::__private::Expect::expect($val, $msg)
+    };
+}
+
+/// The types accepted by [`dbg_expect!`](crate::dbg_expect).
+pub trait Expect {
+    type Output;
+
+    /// Returns the value, or the rendered error if there is one.
+    fn into_result(self) -> Result<Self::Output, Option<String>>;
+
+    #[track_caller]
+    fn expect(self, msg: &str) -> Self::Output;
+}
+
+impl<T> Expect for Option<T> {
+    type Output = T;
+
+    fn into_result(self) -> Result<T, Option<String>> {
+        self.ok_or(None)
+    }
+
+    #[track_caller]
+    fn expect(self, msg: &str) -> T {
+        Option::expect(self, msg)
+    }
+}
+
+impl<T, E: fmt::Debug> Expect for Result<T, E> {
+    type Output = T;
+
+    fn into_result(self) -> Result<T, Option<String>> {
+        self.map_err(|err| Some(format!("{:?}", err)))
+    }
+
+    #[track_caller]
+    fn expect(self, msg: &str) -> T {
+        Result::expect(self, msg)
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub fn expect_failed(msg: &str, err: Option<String>, expr: &str) -> ! {
+    let mut message = msg.to_owned();
+    if let Some(err) = err {
+        let _ = write!(message, ": {}", err);
+    }
+    let _ = write!(message, "\n[dbgonly] expression: `{}`", expr);
+    write_state(&mut message);
+    panic!("{}", message)
+}
+
+/// Appends the current context and watches to a panic message.
+fn write_state(message: &mut String) {
+    if let Some(context) = context::current() {
+        let _ = write!(message, "\n[dbgonly] context: {}", context);
+    }
+    let mut watches = Vec::new();
+    let _ = watch::write_all(&mut watches);
+    if !watches.is_empty() {
+        message.push('\n');
+        message.push_str(String::from_utf8_lossy(&watches).trim_end());
+    }
+}
+
+/// Prints a short backtrace of the current call site, optionally along with
+/// the value of a given expression, and returns the value. This version of
+/// the macro will print nothing and be optimized out in release builds.
+///
+/// This shows who called the function that produced a suspicious value, not
+/// just where the macro is. The backtrace is captured regardless of
+/// `RUST_BACKTRACE`, and the frames of the standard library's runtime and of
+/// this crate are left out:
+///
+/// ```rust
+/// use dbgonly::dbg_bt;
+/// fn checksum(data: &[u8]) -> u32 {
+///     dbg_bt!(data.iter().map(|&b| u32::from(b)).sum())
+/// }
+///
+/// fn load() -> u32 {
+///     checksum(b"abc")
+/// }
+///
+/// assert_eq!(load(), 294);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:3] data.iter().map(|&b| u32::from(b)).sum() = 294
+/// backtrace:
+///    0: main::checksum
+///              at ./src/main.rs:3:5
+///    1: main::load
+///              at ./src/main.rs:7:5
+///    2: main::main
+///              at ./src/main.rs:10:12
+/// ```
+///
+/// Without an expression, `dbg_bt!()` prints only the location and the
+/// backtrace. Function names and locations require debug info.
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_bt {
+    () => {
+        $crate::__emit!(
+            ::core::option::Option::None,
+            ::core::option::Option::Some(format_args!(
+                "backtrace:{}",
+                $crate::__private::short_backtrace()
+            )),
+            &[]
+        )
+    };
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{:#?}\nbacktrace:{}",
+                        &tmp,
+                        $crate::__private::short_backtrace()
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_bt {
+    () => {};
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+}
+
+/// Captures a backtrace and returns its frames on their own lines, without
+/// the frames of this crate and those of the runtime around `main` or a
+/// thread's closure.
+#[inline(never)]
+pub fn short_backtrace() -> String {
+    let backtrace = Backtrace::force_capture();
+    if backtrace.status() != BacktraceStatus::Captured {
+        return " unsupported".to_owned();
+    }
+    let text = backtrace.to_string();
+    // Each frame starts with a numbered line holding its symbol, followed by
+    // lines holding its locations.
+    let mut frames: Vec<(&str, Vec<&str>)> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        match trimmed.split_once(": ") {
+            Some((n, symbol)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+                frames.push((symbol, Vec::new()));
+            }
+            _ => {
+                if let Some((_, lines)) = frames.last_mut() {
+                    lines.push(trimmed);
+                }
+            }
+        }
+    }
+    let internal = |symbol: &str| {
+        symbol.starts_with("std::backtrace")
+            || symbol.starts_with("dbgonly::")
+            || symbol.starts_with("<std::backtrace")
+    };
+    let start = frames
+        .iter()
+        .position(|(symbol, _)| !internal(symbol))
+        .unwrap_or(frames.len());
+    let mut end = frames[start..]
+        .iter()
+        .position(|(symbol, _)| symbol.contains("__rust_begin_short_backtrace"))
+        .map_or(frames.len(), |end| start + end);
+    // The closure or `main` is called through `FnOnce::call_once`.
+    while end > start && frames[end - 1].0.starts_with("core::ops::function::") {
+        end -= 1;
+    }
+    let mut out = String::new();
+    for (i, (symbol, lines)) in frames[start..end].iter().enumerate() {
+        let _ = write!(out, "\n{:>4}: {}", i, symbol);
+        for line in lines {
+            let _ = write!(out, "\n             {}", line);
+        }
+    }
+    out
+}
+
+/// Returns a backtrace on its own lines if backtraces are enabled with
+/// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`, and an empty string otherwise.
+pub fn backtrace() -> String {
+    let backtrace = Backtrace::capture();
+    match backtrace.status() {
+        BacktraceStatus::Captured => {
+            format!("\nstack backtrace:\n{}", backtrace.to_string().trim_end())
+        }
+        _ => String::new(),
+    }
+}
+
+pub struct FiniteProbe<'a, T: ?Sized>(pub &'a T);
+
+/// Any value, which is never flagged as non-finite.
+pub trait NoFloats {
+    fn non_finite(&self) -> bool {
+        false
+    }
+}
+
+impl<T: ?Sized> NoFloats for &FiniteProbe<'_, T> {}
+
+/// A float or a container of floats. This is preferred over [`NoFloats`] by
+/// method resolution, since it is implemented for `FiniteProbe` instead of
+/// `&FiniteProbe`.
+pub trait HasFloats {
+    fn non_finite(&self) -> bool;
+}
+
+impl<T: Floats + ?Sized> HasFloats for FiniteProbe<'_, T> {
+    fn non_finite(&self) -> bool {
+        self.0.any_non_finite()
+    }
+}
+
+/// Types that are or contain floats, which are flagged by
+/// [`set_flag_non_finite`](crate::set_flag_non_finite).
+trait Floats {
+    fn any_non_finite(&self) -> bool;
+}
+
+impl Floats for f32 {
+    fn any_non_finite(&self) -> bool {
+        !self.is_finite()
+    }
+}
+
+impl Floats for f64 {
+    fn any_non_finite(&self) -> bool {
+        !self.is_finite()
+    }
+}
+
+impl<T: Floats + ?Sized> Floats for &T {
+    fn any_non_finite(&self) -> bool {
+        T::any_non_finite(self)
+    }
+}
+
+impl<T: Floats + ?Sized> Floats for &mut T {
+    fn any_non_finite(&self) -> bool {
+        T::any_non_finite(self)
+    }
+}
+
+impl<T: Floats + ?Sized> Floats for Box<T> {
+    fn any_non_finite(&self) -> bool {
+        T::any_non_finite(self)
+    }
+}
+
+impl<T: Floats> Floats for Option<T> {
+    fn any_non_finite(&self) -> bool {
+        self.as_ref().is_some_and(T::any_non_finite)
+    }
+}
+
+impl<T: Floats> Floats for [T] {
+    fn any_non_finite(&self) -> bool {
+        self.iter().any(T::any_non_finite)
+    }
+}
+
+impl<T: Floats, const N: usize> Floats for [T; N] {
+    fn any_non_finite(&self) -> bool {
+        self.iter().any(T::any_non_finite)
+    }
+}
+
+impl<T: Floats> Floats for Vec<T> {
+    fn any_non_finite(&self) -> bool {
+        self.iter().any(T::any_non_finite)
+    }
+}
+
+impl<T: Floats> Floats for VecDeque<T> {
+    fn any_non_finite(&self) -> bool {
+        self.iter().any(T::any_non_finite)
+    }
+}
+
+/// Asserts that a condition holds like `assert!`, with the debugging state
+/// in the panic message. This version of the macro will check nothing and be
+/// optimized out in release builds.
+///
+/// Like [`dbg_expect!`](crate::dbg_expect), the panic message also contains
+/// the current [`context`](crate::context) and the latest values stored with
+/// [`dbg_set!`](crate::dbg_set). A message can be given in the same form as
+/// for `assert!`:
+///
+/// ```rust,no_run
+/// use dbgonly::dbg_assert;
+/// let balance = -5;
+/// dbg_assert!(balance >= 0, "overdrawn by {}", -balance);
+/// ```
+///
+/// This panics with:
+///
+/// ```text,ignore
+/// assertion `balance >= 0` failed: overdrawn by 5
+/// ```
+///
+/// To print the violation without panicking, see
+/// [`dbg_invariant!`](crate::dbg_invariant).
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_assert {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::__private::assert_failed(
+                concat!("assertion `", stringify!($cond), "` failed"),
+                ::core::option::Option::None,
+                &[],
+            )
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::__private::assert_failed(
+                concat!("assertion `", stringify!($cond), "` failed"),
+                ::core::option::Option::Some(format_args!($($arg)+)),
+                &[],
+            )
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_assert {
+    ($cond:expr $(,)?) => {
+        if false {
+            let _: bool = $cond;
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if false {
+            let _: bool = $cond;
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+/// Asserts that two expressions are equal like `assert_eq!`, with both
+/// values pretty-printed in the panic message. This version of the macro will
+/// check nothing and be optimized out in release builds.
+///
+/// The values are labeled with their source and printed with `{:#?}`, which
+/// keeps large structs readable, followed by the debugging state like in
+/// [`dbg_assert!`](crate::dbg_assert):
+///
+/// ```rust,no_run
+/// use dbgonly::dbg_assert_eq;
+/// let parsed = "1,2,4".split(',').collect::<Vec<_>>();
+/// dbg_assert_eq!(parsed, ["1", "2", "3"], "bad input");
+/// ```
+///
+/// This panics with:
+///
+/// ```text,ignore
+/// assertion `parsed == ["1", "2", "3"]` failed: bad input
+///   left: `parsed` = [
+///     "1",
+///     "2",
+///     "4",
+/// ]
+///  right: `["1", "2", "3"]` = [
+///     "1",
+///     "2",
+///     "3",
+/// ]
+/// ```
+///
+/// To print the values without panicking, see
+/// [`dbg_check_eq!`](crate::dbg_check_eq).
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__dbg_assert_cmp!(==, $left, $right, ::core::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::__dbg_assert_cmp!(
+            ==,
+            $left,
+            $right,
+            ::core::option::Option::Some(format_args!($($arg)+))
+        )
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        if false {
+            let _ = $left == $right;
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        if false {
+            let _ = $left == $right;
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+/// Asserts that two expressions are not equal like `assert_ne!`, with both
+/// values pretty-printed in the panic message. This version of the macro will
+/// check nothing and be optimized out in release builds.
+///
+/// See [`dbg_assert_eq!`](crate::dbg_assert_eq) for the form of the message.
+///
+/// ```rust
+/// use dbgonly::dbg_assert_ne;
+/// let (old, new) = ("v1", "v2");
+/// dbg_assert_ne!(old, new, "version was not bumped");
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_assert_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__dbg_assert_cmp!(!=, $left, $right, ::core::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::__dbg_assert_cmp!(
+            !=,
+            $left,
+            $right,
+            ::core::option::Option::Some(format_args!($($arg)+))
+        )
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_assert_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        if false {
+            let _ = $left != $right;
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        if false {
+            let _ = $left != $right;
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dbg_assert_cmp {
+    ($op:tt, $left:expr, $right:expr, $msg:expr) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left $op *right) {
+                    $crate::__private::assert_failed(
+                        concat!(
+                            "assertion `",
+                            stringify!($left),
+                            " ",
+                            stringify!($op),
+                            " ",
+                            stringify!($right),
+                            "` failed"
+                        ),
+                        $msg,
+                        &[
+                            ("left", stringify!($left), left),
+                            ("right", stringify!($right), right),
+                        ],
+                    )
+                }
+            }
+        }
+    };
+}
+
+/// Checks that two expressions are equal and prints both values if they are
+/// not, without panicking. This version of the macro will check nothing and
+/// be optimized out in release builds.
+///
+/// This is the soft version of [`dbg_assert_eq!`](crate::dbg_assert_eq), for
+/// long debugging sessions where one mismatch should not end the run:
+///
+/// ```rust
+/// use dbgonly::dbg_check_eq;
+/// for (input, expected) in [(2, 4), (3, 9), (4, 15)] {
+///     dbg_check_eq!(input * input, expected);
+///     //  ^-- prints: [src/main.rs:3] check `input * input == expected` failed: input * input = 16, expected = 15
+/// }
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_check_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__dbg_check_cmp!(==, $left, $right)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_check_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        if false {
+            let _ = $left == $right;
+        }
+    };
+}
+
+/// Checks that two expressions are not equal and prints both values if they
+/// are, without panicking. This version of the macro will check nothing and
+/// be optimized out in release builds.
+///
+/// This is the soft version of [`dbg_assert_ne!`](crate::dbg_assert_ne):
+///
+/// ```rust
+/// use dbgonly::dbg_check_ne;
+/// let (prev, next) = (7, 7);
+/// dbg_check_ne!(prev, next);
+/// //  ^-- prints: [src/main.rs:3] check `prev != next` failed: prev = 7, next = 7
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_check_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__dbg_check_cmp!(!=, $left, $right)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_check_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        if false {
+            let _ = $left != $right;
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dbg_check_cmp {
+    ($op:tt, $left:expr, $right:expr) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left $op *right) {
+                    $crate::__emit!(
+                        ::core::option::Option::Some(concat!(
+                            "check `",
+                            stringify!($left),
+                            " ",
+                            stringify!($op),
+                            " ",
+                            stringify!($right),
+                            "` failed: ",
+                            stringify!($left)
+                        )),
+                        ::core::option::Option::Some(format_args!(
+                            "{:#?}, {} = {:#?}",
+                            left,
+                            stringify!($right),
+                            right
+                        )),
+                        &[]
+                    )
+                }
+            }
+        }
+    };
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub fn assert_failed(
+    summary: &str,
+    msg: Option<fmt::Arguments<'_>>,
+    operands: &[(&str, &str, &dyn fmt::Debug)],
+) -> ! {
+    let mut message = summary.to_owned();
+    if let Some(msg) = msg {
+        let _ = write!(message, ": {}", msg);
+    }
+    for (label, expr, value) in operands {
+        let _ = write!(message, "\n{:>6}: `{}` = {:#?}", label, expr, value);
+    }
+    write_state(&mut message);
+    panic!("{}", message)
+}
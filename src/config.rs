@@ -0,0 +1,424 @@
+//! Process-wide runtime settings that affect how records are rendered.
+
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Once, OnceLock};
+use std::time::Instant;
+
+static ENABLED: AtomicU8 = AtomicU8::new(UNSET);
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+static REDACT_ADDRESSES: AtomicBool = AtomicBool::new(false);
+static SHOW_RUN_ID: AtomicBool = AtomicBool::new(false);
+static SHOW_THREAD: AtomicBool = AtomicBool::new(false);
+static INDENT_SCOPES: AtomicBool = AtomicBool::new(false);
+static TIMESTAMPS: AtomicU8 = AtomicU8::new(Timestamps::None as u8);
+static START: OnceLock<Instant> = OnceLock::new();
+static FLAG_NON_FINITE: AtomicBool = AtomicBool::new(false);
+static FOLD_MARKERS: AtomicU8 = AtomicU8::new(FoldMarkers::None as u8);
+static MAX_LINES: AtomicUsize = AtomicUsize::new(UNLIMITED);
+static MAX_BYTES: AtomicUsize = AtomicUsize::new(UNLIMITED);
+static MAX_OUTPUT_FROM_ENV: Once = Once::new();
+
+const UNSET: u8 = 0;
+const ON: u8 = 1;
+const OFF: u8 = 2;
+
+const UNLIMITED: usize = usize::MAX;
+
+/// Enables or disables all output at runtime.
+///
+/// Disabled macros still evaluate and return their values, but print,
+/// record and store nothing. Until this is called, output is enabled unless
+/// the `DBGONLY` environment variable is set to `0`, `false` or `off`, so
+/// instrumentation can be left in place and silenced for a single run
+/// without recompiling:
+///
+/// ```sh
+/// DBGONLY=0 cargo run
+/// ```
+///
+/// ```rust
+/// dbgonly::set_enabled(false);
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// //      ^-- prints nothing
+/// # dbgonly::set_enabled(true);
+/// ```
+///
+/// To silence only some call sites, see
+/// [`set_callsite_filter`](crate::set_callsite_filter). `DBGONLY` can also
+/// hold a list of modules to print, see
+/// [`set_module_filter`](crate::set_module_filter).
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(if enabled { ON } else { OFF }, Ordering::Relaxed);
+}
+
+/// Returns whether output is enabled.
+#[inline]
+pub fn enabled() -> bool {
+    match ENABLED.load(Ordering::Relaxed) {
+        ON => true,
+        OFF => false,
+        _ => init_enabled(),
+    }
+}
+
+#[cold]
+fn init_enabled() -> bool {
+    let enabled = !std::env::var("DBGONLY").is_ok_and(|v| {
+        matches!(
+            v.trim().to_ascii_lowercase().as_str(),
+            "0" | "false" | "off"
+        )
+    });
+    // A concurrent `set_enabled` wins over the environment.
+    let state = if enabled { ON } else { OFF };
+    match ENABLED.compare_exchange(UNSET, state, Ordering::Relaxed, Ordering::Relaxed) {
+        Ok(_) => enabled,
+        Err(current) => current == ON,
+    }
+}
+
+/// Enables or disables deterministic output.
+///
+/// In deterministic mode, the parts of a rendered record that vary between
+/// machines and refactors are normalized: file paths are shortened to their
+/// base name, line numbers are replaced with `LINE` and timestamps with
+/// `T+0`. This keeps output that is asserted in snapshots or documentation
+/// stable.
+///
+/// ```rust
+/// dbgonly::set_deterministic(true);
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// //      ^-- prints: [main.rs:LINE] 1 + 1 = 2
+/// # dbgonly::set_deterministic(false);
+/// ```
+pub fn set_deterministic(enabled: bool) {
+    DETERMINISTIC.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether deterministic output is enabled.
+pub fn deterministic() -> bool {
+    DETERMINISTIC.load(Ordering::Relaxed)
+}
+
+/// Enables or disables redaction of memory addresses in printed values.
+///
+/// When enabled, address-like hex literals such as `0x7ffd5c3a1b2c` are
+/// replaced with stable placeholders (`<addr#1>`, `<addr#2>`, ...). Each
+/// distinct address keeps its placeholder for the rest of the run, so two
+/// runs' output can be compared without ASLR noise while still showing which
+/// values point to the same place.
+///
+/// ```rust
+/// dbgonly::set_redact_addresses(true);
+/// let x = 5;
+/// let _ = dbgonly::dbgonly!(&x as *const i32);
+/// //      ^-- prints: [src/main.rs:4] &x as *const i32 = <addr#1>
+/// # dbgonly::set_redact_addresses(false);
+/// ```
+pub fn set_redact_addresses(enabled: bool) {
+    REDACT_ADDRESSES.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether memory addresses are redacted from printed values.
+pub fn redact_addresses() -> bool {
+    REDACT_ADDRESSES.load(Ordering::Relaxed)
+}
+
+/// Enables or disables flagging of NaN and infinite values in printed
+/// records.
+///
+/// When enabled, every value printed by [`dbgonly!`](crate::dbgonly) or
+/// [`dbg_ref!`](crate::dbg_ref) that is a NaN or infinity is marked with
+/// `(non-finite)`, which makes the origin of NaN poisoning easy to spot in
+/// long output. This is checked on the value itself, so it applies to `f32`
+/// and `f64` and to references, `Box`es, `Option`s, arrays, slices, `Vec`s
+/// and `VecDeque`s of them, but not to floats in other types. See
+/// [`dbg_finite!`](crate::dbg_finite) for a macro that prints only
+/// non-finite values.
+///
+/// ```rust
+/// dbgonly::set_flag_non_finite(true);
+/// let _ = dbgonly::dbgonly!(0.0_f64 / 0.0);
+/// //      ^-- prints: [src/main.rs:2] 0.0_f64 / 0.0 = NaN (non-finite)
+/// # dbgonly::set_flag_non_finite(false);
+/// ```
+pub fn set_flag_non_finite(enabled: bool) {
+    FLAG_NON_FINITE.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether NaN and infinite values are flagged in printed records.
+pub fn flag_non_finite() -> bool {
+    FLAG_NON_FINITE.load(Ordering::Relaxed)
+}
+
+/// Enables or disables the [run identifier](crate::run_id) in printed records.
+///
+/// The first time this is enabled, the identifier is also announced on
+/// stderr, so that a run can be matched with its output later on.
+///
+/// ```rust
+/// dbgonly::set_show_run_id(true);
+/// //        ^-- prints: [dbgonly] run 5f0e3a9c
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// //      ^-- prints: [5f0e3a9c src/main.rs:3] 1 + 1 = 2
+/// # dbgonly::set_show_run_id(false);
+/// ```
+pub fn set_show_run_id(enabled: bool) {
+    SHOW_RUN_ID.store(enabled, Ordering::Relaxed);
+    if enabled {
+        static ANNOUNCE: Once = Once::new();
+        ANNOUNCE.call_once(|| eprintln!("[dbgonly] run {}", crate::run_id()));
+    }
+}
+
+/// Returns whether the run identifier is included in printed records.
+pub fn show_run_id() -> bool {
+    SHOW_RUN_ID.load(Ordering::Relaxed)
+}
+
+/// Enables or disables the name of the emitting thread in printed records.
+///
+/// Unnamed threads are shown by their [`ThreadId`](std::thread::ThreadId).
+/// Together with [`set_timestamps`], this makes it possible to attribute
+/// interleaved lines from several threads:
+///
+/// ```rust
+/// dbgonly::set_show_thread(true);
+/// std::thread::Builder::new()
+///     .name("worker-3".into())
+///     .spawn(|| dbgonly::dbgonly!(2 + 3))
+///     .unwrap()
+///     .join()
+///     .unwrap();
+/// //  ^-- prints: [worker-3 src/main.rs:5] 2 + 3 = 5
+/// # dbgonly::set_show_thread(false);
+/// ```
+pub fn set_show_thread(enabled: bool) {
+    SHOW_THREAD.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the name of the emitting thread is included in printed
+/// records.
+pub fn show_thread() -> bool {
+    SHOW_THREAD.load(Ordering::Relaxed)
+}
+
+/// Enables or disables indenting the records printed inside a
+/// [`dbg_scope!`](crate::dbg_scope).
+///
+/// When enabled, every record emitted by the current thread while a scope is
+/// entered is indented by one more level, the same way as inside a
+/// [`dbg_group!`](crate::dbg_group), so recursive algorithms render as a
+/// tree:
+///
+/// ```rust
+/// use dbgonly::{dbg_scope, dbgonly};
+/// fn factorial(n: u32) -> u32 {
+///     dbg_scope!(format!("factorial({})", n));
+///     if n <= 1 {
+///         dbgonly!(1)
+///     } else {
+///         dbgonly!(n * factorial(n - 1))
+///     }
+/// }
+///
+/// dbgonly::set_indent_scopes(true);
+/// assert_eq!(factorial(3), 6);
+/// # dbgonly::set_indent_scopes(false);
+/// ```
+///
+/// This prints to stderr:
+///
+/// ```text,ignore
+/// [src/main.rs:3] enter factorial(3)
+///     [src/main.rs:3] enter factorial(2)
+///         [src/main.rs:3] enter factorial(1)
+///             [src/main.rs:5] 1 = 1
+///         [src/main.rs:3] exit factorial(1) took 9.1µs
+///         [src/main.rs:7] n * factorial(n - 1) = 2
+///     [src/main.rs:3] exit factorial(2) took 31.4µs
+///     [src/main.rs:7] n * factorial(n - 1) = 6
+/// [src/main.rs:3] exit factorial(3) took 52.8µs
+/// ```
+///
+/// Functions annotated with `#[trace]` are always indented.
+pub fn set_indent_scopes(enabled: bool) {
+    INDENT_SCOPES.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the records printed inside a
+/// [`dbg_scope!`](crate::dbg_scope) are indented.
+pub fn indent_scopes() -> bool {
+    INDENT_SCOPES.load(Ordering::Relaxed)
+}
+
+/// The kind of timestamp that printed records start with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Timestamps {
+    /// No timestamp is printed.
+    #[default]
+    None,
+    /// The time since timestamps were first enabled, from a monotonic clock,
+    /// such as `+3.412s`.
+    Monotonic,
+    /// The UTC time of day from the system clock, such as `12:01:03.412`.
+    WallClock,
+}
+
+/// Sets the timestamp that printed records start with.
+///
+/// The time is taken when the record is emitted, not when it is written,
+/// so records kept by a buffering sink still show when they happened. In
+/// [deterministic](set_deterministic) mode, it is printed as `T+0`.
+///
+/// ```rust
+/// use dbgonly::Timestamps;
+/// dbgonly::set_timestamps(Timestamps::WallClock);
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// //      ^-- prints: [12:01:03.412 src/main.rs:3] 1 + 1 = 2
+/// # dbgonly::set_timestamps(Timestamps::None);
+/// ```
+pub fn set_timestamps(timestamps: Timestamps) {
+    if timestamps == Timestamps::Monotonic {
+        START.get_or_init(Instant::now);
+    }
+    TIMESTAMPS.store(timestamps as u8, Ordering::Relaxed);
+}
+
+/// Returns the timestamp that printed records start with.
+pub fn timestamps() -> Timestamps {
+    match TIMESTAMPS.load(Ordering::Relaxed) {
+        1 => Timestamps::Monotonic,
+        2 => Timestamps::WallClock,
+        _ => Timestamps::None,
+    }
+}
+
+/// Returns the instant that monotonic timestamps count from.
+pub(crate) fn start() -> Instant {
+    *START.get_or_init(Instant::now)
+}
+
+/// Markers that let log viewers collapse the output of a
+/// [`dbg_group!`](crate::dbg_group).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FoldMarkers {
+    /// No markers are printed.
+    #[default]
+    None,
+    /// GitHub Actions `::group::` and `::endgroup::` workflow commands. Since
+    /// GitHub does not nest groups, only outermost groups are marked.
+    GitHub,
+    /// GitLab CI `section_start` and `section_end` markers, which nest.
+    GitLab,
+}
+
+impl FoldMarkers {
+    /// Detects the CI system the program is running on from its environment
+    /// variables, falling back to [`FoldMarkers::None`].
+    pub fn detect() -> Self {
+        if std::env::var_os("GITHUB_ACTIONS").is_some_and(|v| v == "true") {
+            FoldMarkers::GitHub
+        } else if std::env::var_os("GITLAB_CI").is_some() {
+            FoldMarkers::GitLab
+        } else {
+            FoldMarkers::None
+        }
+    }
+}
+
+/// Sets the markers that are printed to stderr around every
+/// [`dbg_group!`](crate::dbg_group), so that large groups can be collapsed
+/// in CI logs.
+///
+/// The markers are only printed while records are printed to stderr as well,
+/// not while they go to another [sink](crate::sink) or are
+/// [captured](crate::capture).
+///
+/// ```rust
+/// use dbgonly::FoldMarkers;
+/// dbgonly::set_fold_markers(FoldMarkers::GitHub);
+/// dbgonly::dbg_group!("physics step", {
+///     // ...
+/// });
+/// //  ^-- prints: ::group::physics step
+/// //              [src/main.rs:3] physics step {
+/// //              [src/main.rs:3] } physics step (1.2µs)
+/// //              ::endgroup::
+/// # dbgonly::set_fold_markers(FoldMarkers::None);
+/// ```
+pub fn set_fold_markers(markers: FoldMarkers) {
+    FOLD_MARKERS.store(markers as u8, Ordering::Relaxed);
+}
+
+/// Returns the markers printed around groups.
+pub fn fold_markers() -> FoldMarkers {
+    match FOLD_MARKERS.load(Ordering::Relaxed) {
+        1 => FoldMarkers::GitHub,
+        2 => FoldMarkers::GitLab,
+        _ => FoldMarkers::None,
+    }
+}
+
+/// The most output that a single printed value may take up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MaxOutput {
+    /// The most lines of a value that are printed, or `None` for no limit.
+    pub lines: Option<usize>,
+    /// The most bytes of a value that are printed, or `None` for no limit.
+    pub bytes: Option<usize>,
+}
+
+/// Limits how much of a single value is printed.
+///
+/// The rest of a value that exceeds a limit is cut off and replaced by a
+/// note, so that accidentally printing a huge collection does not flood the
+/// terminal:
+///
+/// ```rust
+/// use dbgonly::MaxOutput;
+/// dbgonly::set_max_output(MaxOutput {
+///     lines: Some(3),
+///     ..MaxOutput::default()
+/// });
+/// let _ = dbgonly::dbgonly!(vec![0u8; 4096]);
+/// //      ^-- prints: [src/main.rs:6] vec![0u8; 4096] = [
+/// //                      0,
+/// //                      0,
+/// //                  ... (truncated, 4095 more lines)
+/// # dbgonly::set_max_output(MaxOutput::default());
+/// ```
+///
+/// Until this is called, the limits are read from the `DBGONLY_MAX_LINES`
+/// and `DBGONLY_MAX_BYTES` environment variables, and there are none if they
+/// are not set. Byte limits never split a character.
+pub fn set_max_output(max: MaxOutput) {
+    // Read the environment first so that it cannot override this later.
+    max_output();
+    MAX_LINES.store(max.lines.unwrap_or(UNLIMITED), Ordering::Relaxed);
+    MAX_BYTES.store(max.bytes.unwrap_or(UNLIMITED), Ordering::Relaxed);
+}
+
+/// Returns the most output that a single printed value may take up.
+pub fn max_output() -> MaxOutput {
+    MAX_OUTPUT_FROM_ENV.call_once(|| {
+        let var = |name| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<usize>().ok())
+        };
+        if let Some(lines) = var("DBGONLY_MAX_LINES") {
+            MAX_LINES.store(lines, Ordering::Relaxed);
+        }
+        if let Some(bytes) = var("DBGONLY_MAX_BYTES") {
+            MAX_BYTES.store(bytes, Ordering::Relaxed);
+        }
+    });
+    let limit = |max: &AtomicUsize| Some(max.load(Ordering::Relaxed)).filter(|&n| n != UNLIMITED);
+    MaxOutput {
+        lines: limit(&MAX_LINES),
+        bytes: limit(&MAX_BYTES),
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+use crate::Callsite;
+
+/// Prints and returns the value of a given expression like
+/// [`dbgonly!`](crate::dbgonly), but can also be used in `const fn`s and
+/// const blocks. This version of the macro will print nothing and be
+/// optimized out in release builds.
+///
+/// When the surrounding code is evaluated at compile time, the macro only
+/// returns the value. Probes can therefore be left in functions that are
+/// sometimes const-evaluated without breaking their compilation:
+///
+/// ```rust
+/// use dbgonly::dbg_const;
+/// const fn area(width: u32, height: u32) -> u32 {
+///     dbg_const!(width * height)
+/// }
+///
+/// const TILE: u32 = area(8, 8);
+/// let room = area(TILE, 3);
+/// # assert_eq!(room, 192);
+/// ```
+///
+/// Stable Rust offers no way for a `const fn` to tell whether it is running
+/// at compile time, so by default the macro prints nothing at runtime either.
+/// With the `nightly` feature, which requires a nightly compiler, calls at
+/// runtime print like `dbgonly!`; above, only `area(TILE, 3)` would print.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_const {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+                    file!(),
+                    line!(),
+                    module_path!(),
+                    ::core::option::Option::Some(stringify!($val)),
+                ).silenced_if($crate::__silenced!());
+                $crate::__private::const_probe(&tmp, &CALLSITE);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_const!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_const {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_const!($val)),+,)
+    };
+}
+
+#[cfg(feature = "nightly")]
+pub const fn const_probe<T: fmt::Debug>(value: &T, callsite: &'static Callsite) {
+    const fn at_compile_time<T: fmt::Debug>(_: &T, _: &'static Callsite) {}
+
+    fn at_runtime<T: fmt::Debug>(value: &T, callsite: &'static Callsite) {
+        if callsite.is_enabled() {
+            crate::__private::emit(callsite, Some(format_args!("{:#?}", value)), &[]);
+        }
+    }
+
+    core::intrinsics::const_eval_select((value, callsite), at_compile_time, at_runtime)
+}
+
+#[cfg(not(feature = "nightly"))]
+pub const fn const_probe<T: fmt::Debug>(_: &T, _: &'static Callsite) {}
@@ -0,0 +1,91 @@
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+thread_local! {
+    static CONTEXT: RefCell<Option<String>> = const { RefCell::new(None) };
+    static FRAMES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Attaches a context string to every record emitted from the current thread.
+///
+/// See [`context`] for a scoped alternative.
+///
+/// The context stays in place until it is replaced by another call or
+/// removed with [`clear_context`]. This makes it easy to tell which request,
+/// user or job an ad-hoc print belongs to without editing every call site:
+///
+/// ```rust
+/// dbgonly::set_context("req=42 user=alice");
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// //      ^-- prints: [src/main.rs:2] 1 + 1 = 2 [req=42 user=alice]
+/// dbgonly::clear_context();
+/// ```
+pub fn set_context(context: impl Into<String>) {
+    CONTEXT.with(|c| *c.borrow_mut() = Some(context.into()));
+}
+
+/// Removes the context set with [`set_context`] for the current thread.
+pub fn clear_context() {
+    CONTEXT.with(|c| *c.borrow_mut() = None);
+}
+
+/// Pushes a context frame for the current thread until the returned guard is
+/// dropped.
+///
+/// Frames nest: every record emitted while a guard is alive carries the
+/// frames of all enclosing guards, after the context set with
+/// [`set_context`]. Since the frame is removed when the guard goes out of
+/// scope, it is also cleaned up on early returns, `?` and panics.
+///
+/// ```rust
+/// let _request = dbgonly::context("req=42");
+/// for frame in 1041..1043 {
+///     let _frame = dbgonly::context(format!("frame {}", frame));
+///     let _ = dbgonly::dbgonly!(frame * 2);
+///     //      ^-- prints: [src/main.rs:4] frame * 2 = 2082 [req=42 > frame 1041]
+/// }
+/// ```
+pub fn context(frame: impl Into<String>) -> ContextGuard {
+    let depth = FRAMES.with(|f| {
+        let mut frames = f.borrow_mut();
+        frames.push(frame.into());
+        frames.len() - 1
+    });
+    ContextGuard {
+        depth,
+        _not_send: PhantomData,
+    }
+}
+
+/// A context frame pushed with [`context`], which is popped when dropped.
+#[must_use = "the context frame is popped as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct ContextGuard {
+    depth: usize,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        FRAMES.with(|f| f.borrow_mut().truncate(self.depth));
+    }
+}
+
+/// Returns the context of the current thread, if any.
+///
+/// Records may still be emitted after the thread-locals have been destroyed,
+/// for example by the exit handler, in which case there is no context.
+pub(crate) fn current() -> Option<String> {
+    let context = CONTEXT.try_with(|c| c.borrow().clone()).ok()?;
+    FRAMES
+        .try_with(|f| {
+            let frames = f.borrow();
+            if frames.is_empty() {
+                return context.clone();
+            }
+            let mut parts: Vec<&str> = context.iter().map(String::as_str).collect();
+            parts.extend(frames.iter().map(String::as_str));
+            Some(parts.join(" > "))
+        })
+        .unwrap_or(context)
+}
@@ -0,0 +1,163 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::{sink, Callsite};
+
+static COUNTERS: Mutex<Vec<&'static Counter>> = Mutex::new(Vec::new());
+static REPORT_AT_EXIT: AtomicBool = AtomicBool::new(false);
+
+/// Counts how often this call site is reached. This version of the macro
+/// will count nothing and be optimized out in release builds.
+///
+/// Nothing is printed when the counter is incremented. The counts of all
+/// call sites are printed, highest first, by
+/// [`report_counts`](crate::report_counts) and
+/// [`dump_state`](crate::dump_state). An optional label names the counter:
+///
+/// ```rust
+/// use dbgonly::dbg_count;
+/// for n in 0..100 {
+///     if n % 7 == 0 {
+///         dbg_count!("multiple of 7");
+///     }
+///     dbg_count!();
+/// }
+/// dbgonly::report_counts();
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [dbgonly] counts (2 call sites):
+///        100 [src/main.rs:6]
+///         15 [src/main.rs:4] multiple of 7
+/// ```
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_count {
+    () => {{
+        static COUNTER: $crate::__private::Counter = $crate::__private::Counter::new(
+            $crate::Callsite::new(
+                file!(),
+                line!(),
+                module_path!(),
+                ::core::option::Option::None,
+            )
+            .silenced_if($crate::__silenced!()),
+        );
+        COUNTER.hit();
+    }};
+    ($label:literal $(,)?) => {{
+        static COUNTER: $crate::__private::Counter = $crate::__private::Counter::new(
+            $crate::Callsite::new(
+                file!(),
+                line!(),
+                module_path!(),
+                ::core::option::Option::Some($label),
+            )
+            .silenced_if($crate::__silenced!()),
+        );
+        COUNTER.hit();
+    }};
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_count {
+    () => {};
+    ($label:literal $(,)?) => {
+        if false {
+            let _: &str = $label;
+        }
+    };
+}
+
+/// The counter of a [`dbg_count!`](crate::dbg_count) call site.
+pub struct Counter {
+    callsite: Callsite,
+    count: AtomicUsize,
+}
+
+impl Counter {
+    pub const fn new(callsite: Callsite) -> Self {
+        Counter {
+            callsite,
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn hit(&'static self) {
+        if !self.callsite.is_enabled() {
+            return;
+        }
+        if self.count.fetch_add(1, Ordering::Relaxed) == 0 {
+            COUNTERS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(self);
+        }
+    }
+}
+
+/// Prints the counts of all [`dbg_count!`](crate::dbg_count) call sites that
+/// were reached to stderr, highest first.
+pub fn report_counts() {
+    let mut stderr = io::stderr().lock();
+    let _ = write_counts(&mut stderr);
+    let _ = stderr.flush();
+}
+
+/// Prints the counts with [`report_counts`] once, when the program exits,
+/// including when a panic unwinds out of `main`.
+///
+/// The report is printed by an exit handler, so only on Unix and Windows, and
+/// not when the program is aborted, for example by a panic with
+/// `panic = "abort"`. Call [`report_counts`] directly on other targets.
+///
+/// ```rust
+/// dbgonly::report_counts_at_exit();
+/// ```
+pub fn report_counts_at_exit() {
+    REPORT_AT_EXIT.store(true, Ordering::Relaxed);
+    sink::flush::install_guards();
+}
+
+#[cfg(any(unix, windows))]
+pub(crate) fn report_at_exit() {
+    if REPORT_AT_EXIT.swap(false, Ordering::Relaxed) {
+        report_counts();
+    }
+}
+
+pub(crate) fn write_counts(w: &mut dyn Write) -> io::Result<()> {
+    let mut counts: Vec<_> = COUNTERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .map(|counter| (counter.count.load(Ordering::Relaxed), &counter.callsite))
+        .collect();
+    if counts.is_empty() {
+        return Ok(());
+    }
+    counts.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| (a.1.file(), a.1.line()).cmp(&(b.1.file(), b.1.line())))
+    });
+    writeln!(
+        w,
+        "[dbgonly] counts ({} call site{}):",
+        counts.len(),
+        if counts.len() == 1 { "" } else { "s" }
+    )?;
+    for (count, callsite) in counts {
+        write!(w, "{:>11} [{}:{}]", count, callsite.file(), callsite.line())?;
+        match callsite.expr() {
+            Some(label) => writeln!(w, " {}", label)?,
+            None => writeln!(w)?,
+        }
+    }
+    Ok(())
+}
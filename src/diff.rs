@@ -0,0 +1,226 @@
+//! Line-based diffs of rendered values.
+
+use std::fmt;
+
+use crate::term;
+
+/// Prints a diff of the `Debug` output of two values and returns them as a
+/// tuple. This version of the macro will print nothing and be optimized out
+/// in release builds.
+///
+/// Both values are pretty-printed with `{:#?}` and compared line by line, so
+/// only the fields that changed between two snapshots of a large struct are
+/// marked with `-` and `+`, with three lines of context around them:
+///
+/// ```rust
+/// use dbgonly::dbg_diff;
+/// #[derive(Debug)]
+/// struct Conn { state: &'static str, retries: u32, peer: &'static str }
+///
+/// let before = Conn { state: "Connecting", retries: 0, peer: "10.0.0.2" };
+/// let after = Conn { state: "Connected", retries: 0, peer: "10.0.0.2" };
+/// let (before, after) = dbg_diff!(before, after);
+/// # let _ = (before, after);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:7] before vs after = 1 hunk, +1 -1
+/// @@ -1,5 +1,5 @@
+///  Conn {
+/// -    state: "Connecting",
+/// +    state: "Connected",
+///      retries: 0,
+///      peer: "10.0.0.2",
+///  }
+/// ```
+///
+/// The diff is colored when the output goes straight to a terminal. To diff
+/// two strings as they are, see [`dbg_diff_str!`](crate::dbg_diff_str).
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_diff {
+    ($old:expr, $new:expr $(,)?) => {
+        match ($old, $new) {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(concat!(
+                        stringify!($old),
+                        " vs ",
+                        stringify!($new)
+                    )),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::debug_diff(&tmp.0, &tmp.1)
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_diff {
+    ($old:expr, $new:expr $(,)?) => {
+        ($old, $new)
+    };
+}
+
+pub fn debug_diff(old: &dyn fmt::Debug, new: &dyn fmt::Debug) -> String {
+    unified(
+        &format!("{:#?}", old),
+        &format!("{:#?}", new),
+        term::terminal().color,
+    )
+}
+
+/// A line in a diff.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Above this many line pairs, the texts are shown as removed and added as a
+/// whole instead of computing a minimal diff.
+const MAX_CELLS: usize = 1 << 20;
+
+/// Computes a minimal line diff from `old` to `new`.
+pub(crate) fn lines<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    if old.len().saturating_mul(new.len()) > MAX_CELLS {
+        return old
+            .iter()
+            .map(|l| Line::Removed(l))
+            .chain(new.iter().map(|l| Line::Added(l)))
+            .collect();
+    }
+    // lcs[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..].
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            out.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            out.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    out.extend(old[i..].iter().map(|l| Line::Removed(l)));
+    out.extend(new[j..].iter().map(|l| Line::Added(l)));
+    out
+}
+
+/// Renders a line diff with `-`, `+` and ` ` prefixes, each line indented by
+/// `indent`.
+pub(crate) fn render(old: &str, new: &str, indent: &str) -> String {
+    let mut out = String::new();
+    for line in lines(old, new) {
+        let (prefix, text) = match line {
+            Line::Same(text) => (' ', text),
+            Line::Removed(text) => ('-', text),
+            Line::Added(text) => ('+', text),
+        };
+        out.push_str(indent);
+        out.push(prefix);
+        out.push(' ');
+        out.push_str(text);
+        out.push('\n');
+    }
+    out
+}
+
+/// The number of unchanged lines shown around each change in a unified diff.
+const CONTEXT: usize = 3;
+
+/// Renders a unified diff from `old` to `new`, preceded by a summary line, with
+/// ANSI colors if `color` is set.
+pub(crate) fn unified(old: &str, new: &str, color: bool) -> String {
+    let lines = lines(old, new);
+    let added = lines.iter().filter(|l| matches!(l, Line::Added(_))).count();
+    let removed = lines
+        .iter()
+        .filter(|l| matches!(l, Line::Removed(_)))
+        .count();
+    if added == 0 && removed == 0 {
+        return "identical".to_owned();
+    }
+    // Group the changes into hunks, merging those whose context overlaps.
+    let changes: Vec<usize> = (0..lines.len())
+        .filter(|&i| !matches!(lines[i], Line::Same(_)))
+        .collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let (red, green, cyan, reset) = if color {
+        ("\x1b[31m", "\x1b[32m", "\x1b[36m", "\x1b[0m")
+    } else {
+        ("", "", "", "")
+    };
+    let mut out = format!(
+        "{} {}, +{} -{}",
+        hunks.len(),
+        if hunks.len() == 1 { "hunk" } else { "hunks" },
+        added,
+        removed
+    );
+    // Line numbers in `old` and `new` at the start of `lines[i]`.
+    let (mut old_line, mut new_line, mut i) = (1, 1, 0);
+    for (start, end) in hunks {
+        for line in &lines[i..start] {
+            old_line += usize::from(!matches!(line, Line::Added(_)));
+            new_line += usize::from(!matches!(line, Line::Removed(_)));
+        }
+        let hunk = &lines[start..end];
+        let old_len = hunk.iter().filter(|l| !matches!(l, Line::Added(_))).count();
+        let new_len = hunk
+            .iter()
+            .filter(|l| !matches!(l, Line::Removed(_)))
+            .count();
+        out.push_str(&format!(
+            "\n{}@@ -{},{} +{},{} @@{}",
+            cyan, old_line, old_len, new_line, new_len, reset
+        ));
+        for line in hunk {
+            match line {
+                Line::Same(text) => out.push_str(&format!("\n {}", text)),
+                Line::Removed(text) => out.push_str(&format!("\n{}-{}{}", red, text, reset)),
+                Line::Added(text) => out.push_str(&format!("\n{}+{}{}", green, text, reset)),
+            }
+        }
+        old_line += old_len;
+        new_line += new_len;
+        i = end;
+    }
+    out
+}
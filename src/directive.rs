@@ -0,0 +1,78 @@
+//! Module filters in the style of `env_logger`, such as
+//! `myapp::parser,myapp::net=off`.
+
+/// A parsed module filter.
+///
+/// A directive is a module path, which enables that module and everything
+/// below it, a module path followed by `=on` or `=off`, or a bare `on` or
+/// `off` that sets the default for modules that no directive matches. The
+/// longest matching path wins. Without a bare level, modules are disabled by
+/// default if any path is enabled and enabled otherwise, so that a list of
+/// paths narrows the output down to them.
+#[derive(Debug)]
+pub(crate) struct Directives {
+    default: bool,
+    modules: Vec<(String, bool)>,
+}
+
+impl Directives {
+    /// A filter that enables every module.
+    pub(crate) const ALL: Directives = Directives {
+        default: true,
+        modules: Vec::new(),
+    };
+
+    /// Parses the filter from the `DBGONLY` environment variable, or enables
+    /// every module if it is not set.
+    pub(crate) fn from_env() -> Self {
+        match std::env::var("DBGONLY") {
+            Ok(spec) => Directives::parse(&spec),
+            Err(_) => Directives::ALL,
+        }
+    }
+
+    /// Parses a comma-separated list of directives. Invalid directives are
+    /// reported on stderr and skipped.
+    pub(crate) fn parse(spec: &str) -> Self {
+        let mut default = None;
+        let mut modules = Vec::new();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((path, level)) => match parse_level(level) {
+                    Some(enabled) if !path.trim().is_empty() => {
+                        modules.push((path.trim().to_owned(), enabled));
+                    }
+                    _ => eprintln!("[dbgonly] ignoring invalid filter `{}`", directive),
+                },
+                None => match parse_level(directive) {
+                    Some(enabled) => default = Some(enabled),
+                    None => modules.push((directive.to_owned(), true)),
+                },
+            }
+        }
+        let default = default.unwrap_or_else(|| !modules.iter().any(|&(_, enabled)| enabled));
+        // Longest paths first, so that the first match is the most specific.
+        modules.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+        Directives { default, modules }
+    }
+
+    /// Returns whether callsites in the given module print.
+    pub(crate) fn allows(&self, module_path: &str) -> bool {
+        self.modules
+            .iter()
+            .find(|(path, _)| {
+                module_path
+                    .strip_prefix(path.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default, |&(_, enabled)| enabled)
+    }
+}
+
+fn parse_level(level: &str) -> Option<bool> {
+    match level.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" => Some(true),
+        "0" | "false" | "off" => Some(false),
+        _ => None,
+    }
+}
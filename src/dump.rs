@@ -0,0 +1,101 @@
+use std::io::{self, Write};
+use std::panic;
+use std::sync::Once;
+
+use crate::{count, sink, stats, timeline, watch};
+
+/// Prints the in-memory debugging state to stderr.
+///
+/// This currently includes the contents of every live
+/// [`Ring`](crate::sink::Ring) buffer, the values stored with
+/// [`dbg_set!`](crate::dbg_set), the [`dbg_event!`](crate::dbg_event)
+/// timeline, the [`dbg_count!`](crate::dbg_count) counts and the
+/// [`dbg_stats!`](crate::dbg_stats) statistics. It can be called from
+/// anywhere, for example from a panic hook, or triggered from outside the
+/// process with [`dump_on_sigusr1`](crate::dump_on_sigusr1).
+pub fn dump_state() {
+    let mut stderr = io::stderr().lock();
+    let _ = sink::ring::dump_all(&mut stderr);
+    let _ = watch::write_all(&mut stderr);
+    let _ = timeline::write_timeline(&mut stderr);
+    let _ = count::write_counts(&mut stderr);
+    let _ = stats::write_stats(&mut stderr);
+    let _ = stderr.flush();
+}
+
+/// Installs a panic hook that prints the records kept by every live
+/// [`Ring`](crate::sink::Ring) buffer before the panic message.
+///
+/// Together with a ring buffer as the sink, this works like a flight
+/// recorder: the most recent records are kept in memory without cluttering
+/// the output, and printed only if the program crashes. To also print every
+/// record as it happens, combine the ring buffer with [`Stderr`] in a
+/// [`Tee`]:
+///
+/// ```rust
+/// use dbgonly::sink::{self, Ring, Stderr, Tee};
+/// sink::set_sink(Ring::new(256));
+/// // or: sink::set_sink(Tee(Stderr, Ring::new(256)));
+/// dbgonly::install_panic_hook();
+/// # sink::reset_sink();
+/// ```
+///
+/// The hook runs before any hook that was installed previously. Calling this
+/// more than once has no additional effect.
+///
+/// [`Stderr`]: crate::sink::Stderr
+/// [`Tee`]: crate::sink::Tee
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let mut stderr = io::stderr().lock();
+            let _ = sink::ring::dump_all(&mut stderr);
+            let _ = stderr.flush();
+            drop(stderr);
+            previous(info);
+        }));
+    });
+}
+
+/// Dumps the in-memory debugging state with [`dump_state`] whenever the
+/// process receives `SIGUSR1`.
+///
+/// This is handy for finding out what a wedged process was doing without
+/// attaching a debugger:
+///
+/// ```text,ignore
+/// kill -USR1 $(pidof my-app)
+/// ```
+///
+/// The state is printed from a background thread, not from the signal
+/// handler itself. Calling this more than once has no additional effect.
+///
+/// ```rust,no_run
+/// dbgonly::dump_on_sigusr1().expect("cannot register signal handler");
+/// ```
+#[cfg(all(feature = "signal", unix))]
+pub fn dump_on_sigusr1() -> io::Result<()> {
+    use std::sync::{Mutex, PoisonError};
+
+    use signal_hook::consts::SIGUSR1;
+    use signal_hook::iterator::Signals;
+
+    static REGISTERED: Mutex<bool> = Mutex::new(false);
+
+    let mut registered = REGISTERED.lock().unwrap_or_else(PoisonError::into_inner);
+    if *registered {
+        return Ok(());
+    }
+    let mut signals = Signals::new([SIGUSR1])?;
+    std::thread::Builder::new()
+        .name("dbgonly-signal".to_owned())
+        .spawn(move || {
+            for _ in signals.forever() {
+                dump_state();
+            }
+        })?;
+    *registered = true;
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+use std::panic::Location;
+
+use crate::callsite::callsite_at;
+use crate::{context, sink, Record};
+
+/// Reports a failing input found by a property-testing framework through the
+/// active [`Sink`](crate::Sink).
+///
+/// Frameworks like proptest and quickcheck shrink a failing input down to a
+/// minimal one and print it with `println!`. Routing it through this
+/// function instead gives it the same treatment as any other record:
+/// redaction, context, run identifiers and structured sinks all apply. The
+/// record is attributed to the caller's location and uses `label` in place
+/// of the expression. Like the macros, this prints nothing in release
+/// builds, and the call site can be turned off with
+/// [`set_enabled`](crate::set_enabled), the `DBGONLY` directives and
+/// [`set_callsite_filter`](crate::set_callsite_filter). Since this is a
+/// function, `--cfg dbgonly_disable` only silences it when this crate is
+/// built with it, such as through `RUSTFLAGS`.
+///
+/// For example, with proptest's `TestRunner`:
+///
+/// ```rust,ignore
+/// let mut runner = TestRunner::default();
+/// if let Err(TestError::Fail(reason, input)) = runner.run(&strategy, check) {
+///     dbgonly::report_failing_input("minimal failing input", &input);
+///     panic!("{}", reason);
+/// }
+/// ```
+///
+/// or, for a single value:
+///
+/// ```rust
+/// dbgonly::report_failing_input("shrunk", &-7);
+/// //        ^-- prints: [src/main.rs:1] shrunk = -7
+/// ```
+#[track_caller]
+pub fn report_failing_input<T: fmt::Debug + ?Sized>(label: &str, input: &T) {
+    if !cfg!(dbgonly_active) || cfg!(dbgonly_disable) {
+        return;
+    }
+    let location = Location::caller();
+    // The label is not `'static`, so the call site is interned without it.
+    if !callsite_at(location.file(), location.line(), "", None).is_enabled() {
+        return;
+    }
+    let context = context::current();
+    sink::emit(&Record::new(
+        location.file(),
+        location.line(),
+        "",
+        Some(label),
+        Some(format_args!("{:#?}", input)),
+        &[],
+        context.as_deref(),
+    ));
+}
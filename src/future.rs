@@ -0,0 +1,161 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use crate::{config, context, sink, Callsite, Record};
+
+/// Wraps a future so that awaiting it prints when it starts, how long it took
+/// to complete and how often it was polled, along with its output. This
+/// version of the macro will print nothing and return the future unchanged
+/// in release builds.
+///
+/// Wrapping an async expression in [`dbgonly!`](crate::dbgonly) only shows
+/// the opaque future, so this macro goes where the future is awaited:
+///
+/// ```rust
+/// use dbgonly::dbg_await;
+/// async fn fetch(id: u32) -> Result<String, ()> {
+///     Ok(format!("user {}", id))
+/// }
+///
+/// # fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+/// #     struct Noop;
+/// #     impl std::task::Wake for Noop {
+/// #         fn wake(self: std::sync::Arc<Self>) {}
+/// #     }
+/// #     let waker = std::sync::Arc::new(Noop).into();
+/// #     let mut cx = std::task::Context::from_waker(&waker);
+/// #     let mut fut = std::pin::pin!(fut);
+/// #     loop {
+/// #         if let std::task::Poll::Ready(v) = fut.as_mut().poll(&mut cx) {
+/// #             return v;
+/// #         }
+/// #     }
+/// # }
+/// # block_on(async {
+/// let user = dbg_await!(fetch(7)).await;
+/// //         ^-- prints: [src/main.rs:6] fetch(7) started
+/// //                     [src/main.rs:6] fetch(7) took 1.3ms (1 poll) = Ok(
+/// //                         "user 7",
+/// //                     )
+/// # assert!(user.is_ok());
+/// # });
+/// ```
+///
+/// Anything that implements [`IntoFuture`](std::future::IntoFuture) can be
+/// wrapped. The time is measured from the first poll, so it includes the
+/// time the future spent waiting to be woken up. In
+/// [deterministic](crate::set_deterministic) mode, it is printed as
+/// `ELAPSED`.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_await {
+    ($fut:expr $(,)?) => {
+        $crate::__private::DbgFuture::new(
+            {
+                static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+                    file!(),
+                    line!(),
+                    module_path!(),
+                    ::core::option::Option::Some(stringify!($fut)),
+                )
+                .silenced_if($crate::__silenced!());
+                &CALLSITE
+            },
+            ::core::future::IntoFuture::into_future($fut),
+        )
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_await {
+    ($fut:expr $(,)?) => {
+        $fut
+    };
+}
+
+/// A future wrapped by [`dbg_await!`](crate::dbg_await).
+pub struct DbgFuture<F> {
+    callsite: &'static Callsite,
+    // Boxed so that the wrapper can be polled without unsafe pin projection.
+    inner: Pin<Box<F>>,
+    start: Option<Instant>,
+    polls: usize,
+}
+
+impl<F: Future> DbgFuture<F> {
+    pub fn new(callsite: &'static Callsite, inner: F) -> Self {
+        DbgFuture {
+            callsite,
+            inner: Box::pin(inner),
+            start: None,
+            polls: 0,
+        }
+    }
+}
+
+impl<F> Future for DbgFuture<F>
+where
+    F: Future,
+    F::Output: fmt::Debug,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = &mut *self;
+        let start = *this.start.get_or_insert_with(|| {
+            if this.callsite.is_enabled() {
+                emit_started(this.callsite);
+            }
+            Instant::now()
+        });
+        this.polls += 1;
+        let output = this.inner.as_mut().poll(cx);
+        if let Poll::Ready(value) = &output {
+            if this.callsite.is_enabled() {
+                emit_ready(this.callsite, start, this.polls, value);
+            }
+        }
+        output
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn emit_started(callsite: &'static Callsite) {
+    let expr = format!("{} started", callsite.expr().unwrap_or_default());
+    emit(callsite, &expr, None);
+}
+
+#[cold]
+#[inline(never)]
+fn emit_ready(callsite: &'static Callsite, start: Instant, polls: usize, value: &dyn fmt::Debug) {
+    let elapsed = start.elapsed();
+    let expr = callsite.expr().unwrap_or_default();
+    let polls = match polls {
+        1 => "1 poll".to_owned(),
+        n => format!("{} polls", n),
+    };
+    let expr = if config::deterministic() {
+        format!("{} took ELAPSED ({})", expr, polls)
+    } else {
+        format!("{} took {:?} ({})", expr, elapsed, polls)
+    };
+    emit(callsite, &expr, Some(format_args!("{:#?}", value)));
+}
+
+fn emit(callsite: &'static Callsite, expr: &str, value: Option<fmt::Arguments<'_>>) {
+    let context = context::current();
+    sink::emit(&Record::new(
+        callsite.file(),
+        callsite.line(),
+        callsite.module_path(),
+        Some(expr),
+        value,
+        &[],
+        context.as_deref(),
+    ));
+}
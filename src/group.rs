@@ -0,0 +1,202 @@
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{capture, config, sink, Callsite, FoldMarkers};
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Groups the output produced while evaluating a block and returns the
+/// block's value. This version of the macro will print nothing and be
+/// optimized out in release builds.
+///
+/// A header is printed before the block and a footer with the elapsed time
+/// after it, and every record emitted by the current thread in between is
+/// indented by one level. Groups nest:
+///
+/// ```rust
+/// use dbgonly::{dbg_group, dbgonly};
+/// let energy = dbg_group!("physics step", {
+///     let v = dbgonly!(3.0_f64);
+///     dbg_group!("collisions", {
+///         dbgonly!(v * 2.0);
+///     });
+///     v * v / 2.0
+/// });
+/// assert_eq!(energy, 4.5);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:2] physics step {
+///     [src/main.rs:3] 3.0_f64 = 3.0
+///     [src/main.rs:4] collisions {
+///         [src/main.rs:5] v * 2.0 = 6.0
+///     [src/main.rs:4] } collisions (11.2µs)
+/// [src/main.rs:2] } physics step (48.9µs)
+/// ```
+///
+/// The footer is also printed when the block is left early with `return`,
+/// `?` or a panic. In [deterministic](crate::set_deterministic) mode, the
+/// elapsed time is printed as `ELAPSED`. To make groups collapsible in CI
+/// logs, see [`set_fold_markers`](crate::set_fold_markers).
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_group {
+    ($label:expr, $body:expr $(,)?) => {{
+        static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+            file!(),
+            line!(),
+            module_path!(),
+            ::core::option::Option::None,
+        )
+        .silenced_if($crate::__silenced!());
+        let _group = $crate::__private::Group::enter(&CALLSITE, $label);
+        $body
+    }};
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_group {
+    ($label:expr, $body:expr $(,)?) => {{
+        if false {
+            let _ = &$label;
+        }
+        $body
+    }};
+}
+
+/// An open [`dbg_group!`], which prints the footer and outdents when dropped.
+pub struct Group {
+    callsite: &'static Callsite,
+    label: String,
+    start: Instant,
+    /// The depth to restore on exit, if the header was printed.
+    depth: Option<usize>,
+    markers: FoldMarkers,
+    section: u64,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Group {
+    pub fn enter(callsite: &'static Callsite, label: impl fmt::Display) -> Self {
+        static SECTIONS: AtomicU64 = AtomicU64::new(0);
+
+        let label = label.to_string();
+        let mut depth = None;
+        let mut markers = FoldMarkers::None;
+        let section = SECTIONS.fetch_add(1, Ordering::Relaxed);
+        if callsite.is_enabled() {
+            markers = match config::fold_markers() {
+                // The markers only make sense next to the records in the log.
+                _ if !sink::writes_to_stderr() || capture::capturing() => FoldMarkers::None,
+                // GitHub ends the open group when another one starts.
+                FoldMarkers::GitHub if self::depth() > 0 => FoldMarkers::None,
+                markers => markers,
+            };
+            // Held so that the marker and the header after it stay together.
+            let _stderr = write_marker(markers, |stderr, markers| match markers {
+                FoldMarkers::GitHub => writeln!(stderr, "::group::{}", label.replace('\n', " ")),
+                _ => writeln!(
+                    stderr,
+                    "\x1b[0Ksection_start:{}:dbgonly_{}[collapsed=true]\r\x1b[0K{}",
+                    unix_time(),
+                    section,
+                    label.replace('\n', " ")
+                ),
+            });
+            crate::__private::emit(callsite, Some(format_args!("{} {{", label)), &[]);
+            depth = Some(indent());
+        }
+        Group {
+            callsite,
+            label,
+            start: Instant::now(),
+            depth,
+            markers,
+            section,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for Group {
+    fn drop(&mut self) {
+        let Some(depth) = self.depth else {
+            return;
+        };
+        let elapsed = self.start.elapsed();
+        restore_depth(depth);
+        // Held so that the footer and the marker after it stay together.
+        let _stderr = (self.markers != FoldMarkers::None).then(|| io::stderr().lock());
+        if config::deterministic() {
+            crate::__private::emit(
+                self.callsite,
+                Some(format_args!("}} {} (ELAPSED)", self.label)),
+                &[],
+            );
+        } else {
+            crate::__private::emit(
+                self.callsite,
+                Some(format_args!("}} {} ({:?})", self.label, elapsed)),
+                &[],
+            );
+        }
+        write_marker(self.markers, |stderr, markers| match markers {
+            FoldMarkers::GitHub => writeln!(stderr, "::endgroup::"),
+            _ => writeln!(
+                stderr,
+                "\x1b[0Ksection_end:{}:dbgonly_{}\r\x1b[0K",
+                unix_time(),
+                self.section
+            ),
+        });
+    }
+}
+
+/// Writes a fold marker to stderr unless `markers` is `None`, and returns the
+/// stderr lock, so that the record next to the marker can be written while
+/// holding it. The lock is reentrant, so the default sink can take it again.
+fn write_marker(
+    markers: FoldMarkers,
+    write: impl FnOnce(&mut io::StderrLock<'static>, FoldMarkers) -> io::Result<()>,
+) -> Option<io::StderrLock<'static>> {
+    if markers == FoldMarkers::None {
+        return None;
+    }
+    let mut stderr = io::stderr().lock();
+    let _ = write(&mut stderr, markers);
+    Some(stderr)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Indents the records of the current thread by one more level and returns
+/// the previous depth, which has to be passed to [`restore_depth`].
+pub(crate) fn indent() -> usize {
+    let depth = DEPTH.with(Cell::get);
+    DEPTH.with(|d| d.set(depth + 1));
+    depth
+}
+
+pub(crate) fn restore_depth(depth: usize) {
+    DEPTH.with(|d| d.set(depth));
+}
+
+/// Returns the number of groups the current thread is nested in.
+pub(crate) fn depth() -> usize {
+    DEPTH.try_with(Cell::get).unwrap_or(0)
+}
@@ -0,0 +1,156 @@
+use std::hash::{Hash, Hasher};
+
+/// Prints a short fingerprint of the value of a given expression and returns
+/// the value. This version of the macro will print nothing and be optimized
+/// out in release builds.
+///
+/// Instead of the full `Debug` output, a 64-bit hash of the value's [`Hash`]
+/// implementation is printed. This is useful for checking whether two points
+/// in a program see the same data when the data itself is too large to read:
+///
+/// ```rust
+/// use dbgonly::dbg_hash;
+/// let buf = vec![0u8; 1 << 20];
+/// let buf = dbg_hash!(buf);
+/// //        ^-- prints: [src/main.rs:3] buf = #c8789d54ac769d55
+/// # assert_eq!(buf.len(), 1 << 20);
+/// ```
+///
+/// The hash is stable across runs of the same build, but not across targets
+/// with a different endianness or pointer width. Like [`dbgonly!`], multiple
+/// values are treated as a tuple.
+///
+/// [`dbgonly!`]: crate::dbgonly
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_hash {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "#{:016x}", $crate::__private::fingerprint(&tmp))), &[]);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_hash!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_hash {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_hash!($val)),+,)
+    };
+}
+
+/// Prints the length and CRC-32 checksum of a byte buffer and returns the
+/// buffer. This version of the macro will print nothing and be optimized out
+/// in release builds.
+///
+/// The expression can be anything that implements `AsRef<[u8]>`, such as
+/// `Vec<u8>`, `&[u8]`, arrays or strings. The checksum is the common CRC-32
+/// (IEEE 802.3) used by zlib, gzip and PNG, so it can be compared against
+/// the output of other tools:
+///
+/// ```rust
+/// use dbgonly::dbg_crc;
+/// let sent = b"The quick brown fox jumps over the lazy dog".to_vec();
+/// let sent = dbg_crc!(sent);
+/// //         ^-- prints: [src/main.rs:3] sent = len 43, crc32 414fa339
+/// # assert_eq!(sent.len(), 43);
+/// ```
+///
+/// Like [`dbgonly!`], multiple values are treated as a tuple.
+///
+/// [`dbgonly!`]: crate::dbgonly
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_crc {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                let bytes: &[u8] = ::core::convert::AsRef::as_ref(&tmp);
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "len {}, crc32 {:08x}", bytes.len(), $crate::__private::crc32(bytes))), &[]);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_crc!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_crc {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_crc!($val)),+,)
+    };
+}
+
+/// 64-bit FNV-1a, chosen over `DefaultHasher` because its output is
+/// guaranteed not to change between Rust releases.
+struct Fnv64(u64);
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+pub fn fingerprint<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = Fnv64(0xcbf2_9ce4_8422_2325);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
@@ -0,0 +1,172 @@
+use std::fmt::{self, Write};
+
+/// Prints integers in hexadecimal and binary and byte buffers as a hexdump,
+/// and returns the value. This version of the macro will print nothing and be
+/// optimized out in release builds.
+///
+/// Integers are zero-padded to the width of their type, and negative numbers
+/// are shown in two's complement. Byte slices, arrays and vectors are shown
+/// with offsets, sixteen bytes per line, next to their printable ASCII
+/// characters, which makes protocol and file format parsers much easier to
+/// follow than the `Debug` output of a `Vec<u8>`:
+///
+/// ```rust
+/// use dbgonly::dbg_hex;
+/// let flags = dbg_hex!(0x2c_u8);
+/// //          ^-- prints: [src/main.rs:2] 0x2c_u8 = 0x2c (0b0010_1100, 44)
+/// let packet = dbg_hex!(b"GET / HTTP/1.1\r\n".to_vec());
+/// # assert_eq!((flags, packet.len()), (44, 16));
+/// ```
+///
+/// The packet prints as:
+///
+/// ```text,ignore
+/// [src/main.rs:4] b"GET / HTTP/1.1\r\n".to_vec() = 16 bytes
+/// 0000: 47 45 54 20 2f 20 48 54 54 50 2f 31 2e 31 0d 0a |GET / HTTP/1.1..|
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_hex {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::HexValue(&tmp)
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_hex!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_hex {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _ = $crate::__private::HexValue(&tmp);
+                }
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_hex!($val)),+,)
+    };
+}
+
+/// Values accepted by [`dbg_hex!`](crate::dbg_hex).
+pub trait Hex {
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+macro_rules! hex_integers {
+    ($($ty:ident => $unsigned:ident,)*) => {
+        $(
+            impl Hex for $ty {
+                fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    let bits = <$unsigned>::BITS as usize;
+                    write!(f, "{:#0width$x} (0b", *self as $unsigned, width = bits / 4 + 2)?;
+                    let binary = format!("{:0width$b}", *self as $unsigned, width = bits);
+                    for (i, chunk) in binary.as_bytes().chunks(4).enumerate() {
+                        if i > 0 {
+                            f.write_char('_')?;
+                        }
+                        // Binary digits are ASCII.
+                        f.write_str(std::str::from_utf8(chunk).unwrap_or_default())?;
+                    }
+                    write!(f, ", {})", self)
+                }
+            }
+        )*
+    };
+}
+
+hex_integers! {
+    u8 => u8,
+    u16 => u16,
+    u32 => u32,
+    u64 => u64,
+    u128 => u128,
+    usize => usize,
+    i8 => u8,
+    i16 => u16,
+    i32 => u32,
+    i64 => u64,
+    i128 => u128,
+    isize => usize,
+}
+
+impl Hex for [u8] {
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes", self.len())?;
+        f.write_str(&dump(self))
+    }
+}
+
+impl<const N: usize> Hex for [u8; N] {
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self[..].fmt_hex(f)
+    }
+}
+
+impl Hex for Vec<u8> {
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self[..].fmt_hex(f)
+    }
+}
+
+impl<T: Hex + ?Sized> Hex for &T {
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt_hex(self, f)
+    }
+}
+
+impl<T: Hex + ?Sized> Hex for &mut T {
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt_hex(self, f)
+    }
+}
+
+pub struct HexValue<'a, T: ?Sized>(pub &'a T);
+
+impl<T: Hex + ?Sized> fmt::Display for HexValue<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_hex(f)
+    }
+}
+
+/// Renders bytes as hexdump lines with offsets, hex bytes and their printable
+/// ASCII characters, each line preceded by a line break.
+pub(crate) fn dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        let _ = write!(out, "\n{:04x}: ", i * 16);
+        for byte in chunk {
+            let _ = write!(out, "{:02x} ", byte);
+        }
+        for _ in chunk.len()..16 {
+            out.push_str("   ");
+        }
+        out.push('|');
+        out.extend(chunk.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        out.push('|');
+    }
+    out
+}
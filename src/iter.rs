@@ -0,0 +1,123 @@
+use std::fmt;
+
+use crate::Callsite;
+
+/// Wraps an iterator so that every item is printed with its index as it is
+/// yielded, and returns the wrapped iterator. This version of the macro will
+/// print nothing and return the iterator as is in release builds.
+///
+/// Unlike collecting the items first, this leaves the laziness of the chain
+/// intact, so items that are never asked for are never printed:
+///
+/// ```rust
+/// use dbgonly::dbg_iter;
+/// let words = ["apple", "kiwi", "banana", "fig"];
+/// let long = dbg_iter!(words.iter().filter(|w| w.len() > 3))
+///     .map(|w| w.len())
+///     .take(2)
+///     .collect::<Vec<_>>();
+/// assert_eq!(long, [5, 4]);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:3] words.iter().filter(|w| w.len() > 3) = [0] "apple"
+/// [src/main.rs:3] words.iter().filter(|w| w.len() > 3) = [1] "kiwi"
+/// ```
+///
+/// When the iterator is exhausted, the number of items is printed once. The
+/// items have to implement `Debug`. Since the returned type differs between
+/// debug and release builds, it should not be named.
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_iter {
+    ($iter:expr $(,)?) => {
+        $crate::__private::DbgIter::new($iter, {
+            static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+                file!(),
+                line!(),
+                module_path!(),
+                ::core::option::Option::Some(stringify!($iter)),
+            )
+            .silenced_if($crate::__silenced!());
+            &CALLSITE
+        })
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_iter {
+    ($iter:expr $(,)?) => {
+        $iter
+    };
+}
+
+/// The iterator returned by [`dbg_iter!`](crate::dbg_iter).
+#[derive(Debug)]
+pub struct DbgIter<I> {
+    iter: I,
+    callsite: &'static Callsite,
+    index: usize,
+    done: bool,
+}
+
+impl<I: Iterator> DbgIter<I>
+where
+    I::Item: fmt::Debug,
+{
+    pub fn new(iter: I, callsite: &'static Callsite) -> Self {
+        DbgIter {
+            iter,
+            callsite,
+            index: 0,
+            done: false,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for DbgIter<I>
+where
+    I::Item: fmt::Debug,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.iter.next();
+        match &item {
+            Some(item) => {
+                if self.callsite.is_enabled() {
+                    crate::__private::emit(
+                        self.callsite,
+                        Some(format_args!("[{}] {:#?}", self.index, item)),
+                        &[],
+                    );
+                }
+                self.index += 1;
+            }
+            None if !self.done => {
+                self.done = true;
+                if self.callsite.is_enabled() {
+                    crate::__private::emit(
+                        self.callsite,
+                        Some(format_args!(
+                            "done after {} {}",
+                            self.index,
+                            if self.index == 1 { "item" } else { "items" }
+                        )),
+                        &[],
+                    );
+                }
+            }
+            None => {}
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
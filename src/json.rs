@@ -0,0 +1,91 @@
+use serde::Serialize;
+
+/// Prints the value of a given expression as JSON and returns the value. This
+/// version of the macro will print nothing and be optimized out in release
+/// builds.
+///
+/// The value is serialized with `serde_json`, so it only has to implement
+/// `Serialize`, not `Debug`, as is often the case for types that derive it,
+/// and the output can be fed to tools like `jq` or diffed directly:
+///
+/// ```rust
+/// use dbgonly::dbg_json;
+/// use std::collections::BTreeMap;
+///
+/// let stock = dbg_json!(BTreeMap::from([("tea", 3), ("coffee", 0)]));
+/// //          ^-- prints: [src/main.rs:4] BTreeMap::from([("tea", 3), ("coffee", 0)]) = {
+/// //                        "coffee": 0,
+/// //                        "tea": 3
+/// //                      }
+/// let stock = dbg_json!(compact: stock);
+/// //          ^-- prints: [src/main.rs:9] stock = {"coffee":0,"tea":3}
+/// # assert_eq!(stock["tea"], 3);
+/// ```
+///
+/// If serialization fails, the error is printed instead of the value. Like
+/// [`dbgonly!`](crate::dbgonly), multiple values are treated as a tuple.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_json {
+    (compact: $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::to_json(&tmp, false)
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::to_json(&tmp, true)
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_json!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_json {
+    (compact: $val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_json!($val)),+,)
+    };
+}
+
+/// Serializes a value as JSON, or describes why that failed.
+pub fn to_json<T: Serialize + ?Sized>(value: &T, pretty: bool) -> String {
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    json.unwrap_or_else(|e| format!("<cannot serialize: {}>", e))
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+
+/// Prints a SIMD vector lane by lane and returns it. This version of the
+/// macro will print nothing and be optimized out in release builds.
+///
+/// The `Debug` output of the `core::arch` vector types is a flat list of
+/// numbers or nothing useful at all, which makes vectorized code hard to
+/// follow. Every lane is printed with its index instead, and the lane type can
+/// be given as a second argument to view the same bits differently:
+///
+/// ```rust
+/// use dbgonly::dbg_lanes;
+/// let v = dbg_lanes!([1.0_f32, 2.0, 3.0, 4.0]);
+/// //      ^-- prints: [src/main.rs:2] [1.0_f32, 2.0, 3.0, 4.0] = f32x4 [0: 1.0, 1: 2.0, 2: 3.0, 3: 4.0]
+/// let v = dbg_lanes!(v, u32);
+/// //      ^-- prints: [src/main.rs:4] v = u32x4 [0: 1065353216, 1: 1073741824, 2: 1077936128, 3: 1082130432]
+/// # assert_eq!(v[3], 4.0);
+/// ```
+///
+/// The expression can be an array of integers or floats, or one of the
+/// `core::arch` vector types of x86, x86-64, AArch64 and WebAssembly. Integer
+/// vectors such as `__m128i` have no lane type of their own and are shown as
+/// `i32` lanes unless another one is given. With `portable_simd`, pass the
+/// array of a `Simd` vector, as in `dbg_lanes!(v.to_array())`.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_lanes {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::lanes(&tmp)
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+    ($val:expr, $lane:ty $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::lanes_as::<$lane, _>(&tmp)
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_lanes {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _ = $crate::__private::lanes(&tmp);
+                }
+                tmp
+            }
+        }
+    };
+    ($val:expr, $lane:ty $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _ = $crate::__private::lanes_as::<$lane, _>(&tmp);
+                }
+                tmp
+            }
+        }
+    };
+}
+
+/// The lane types of vectors printed by [`dbg_lanes!`](crate::dbg_lanes).
+///
+/// # Safety
+///
+/// Every bit pattern of the size of the type must be a valid value of it.
+pub unsafe trait Lane: Copy + fmt::Debug {
+    /// The name of the type, as in `f32`.
+    const NAME: &'static str;
+}
+
+/// The vector types accepted by [`dbg_lanes!`](crate::dbg_lanes).
+///
+/// # Safety
+///
+/// The type must not contain padding or other uninitialized bytes.
+pub unsafe trait Vector: Copy {
+    /// The type of the lanes shown when no other type is given.
+    type Lane: Lane;
+}
+
+macro_rules! lanes {
+    ($($ty:ident)*) => {
+        $(
+            // SAFETY: Every bit pattern is a valid integer or float.
+            unsafe impl Lane for $ty {
+                const NAME: &'static str = stringify!($ty);
+            }
+        )*
+    };
+}
+
+lanes!(i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64);
+
+// SAFETY: Arrays of lanes have no padding between their elements.
+unsafe impl<T: Lane, const N: usize> Vector for [T; N] {
+    type Lane = T;
+}
+
+macro_rules! vectors {
+    ($arch:ident: $($ty:ident => $lane:ty,)*) => {
+        $(
+            // SAFETY: Vector registers have no padding.
+            unsafe impl Vector for ::core::arch::$arch::$ty {
+                type Lane = $lane;
+            }
+        )*
+    };
+}
+
+#[cfg(target_arch = "x86")]
+vectors! {
+    x86:
+    __m128 => f32,
+    __m128d => f64,
+    __m128i => i32,
+    __m256 => f32,
+    __m256d => f64,
+    __m256i => i32,
+}
+
+#[cfg(target_arch = "x86_64")]
+vectors! {
+    x86_64:
+    __m128 => f32,
+    __m128d => f64,
+    __m128i => i32,
+    __m256 => f32,
+    __m256d => f64,
+    __m256i => i32,
+}
+
+#[cfg(target_arch = "aarch64")]
+vectors! {
+    aarch64:
+    int8x8_t => i8,
+    int8x16_t => i8,
+    int16x4_t => i16,
+    int16x8_t => i16,
+    int32x2_t => i32,
+    int32x4_t => i32,
+    int64x1_t => i64,
+    int64x2_t => i64,
+    uint8x8_t => u8,
+    uint8x16_t => u8,
+    uint16x4_t => u16,
+    uint16x8_t => u16,
+    uint32x2_t => u32,
+    uint32x4_t => u32,
+    uint64x1_t => u64,
+    uint64x2_t => u64,
+    float32x2_t => f32,
+    float32x4_t => f32,
+    float64x1_t => f64,
+    float64x2_t => f64,
+}
+
+#[cfg(target_arch = "wasm32")]
+vectors! {
+    wasm32:
+    v128 => i32,
+}
+
+pub struct Lanes<'a, V, L>(&'a V, PhantomData<L>);
+
+pub fn lanes<V: Vector>(vector: &V) -> Lanes<'_, V, V::Lane> {
+    Lanes(vector, PhantomData)
+}
+
+pub fn lanes_as<L: Lane, V: Vector>(vector: &V) -> Lanes<'_, V, L> {
+    Lanes(vector, PhantomData)
+}
+
+impl<V: Vector, L: Lane> fmt::Display for Lanes<'_, V, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = mem::size_of::<V>() / mem::size_of::<L>();
+        let ptr = (self.0 as *const V).cast::<L>();
+        write!(f, "{}x{} [", L::NAME, count)?;
+        for i in 0..count {
+            // SAFETY: The lane lies within the vector, whose bytes are all
+            // initialized, and every bit pattern is a valid `L`.
+            let lane = unsafe { ptr.add(i).read_unaligned() };
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}: {:?}", i, lane)?;
+        }
+        f.write_str("]")
+    }
+}
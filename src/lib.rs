@@ -3,6 +3,488 @@
  * https://github.com/rust-lang/rust/blob/master/library/std/src/macros.rs#L212-L361
  */
 
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "nightly", feature(core_intrinsics, const_eval_select))]
+#![cfg_attr(feature = "nightly", allow(internal_features))]
+
+#[cfg(feature = "std")]
+use core::fmt;
+
+#[cfg(not(feature = "std"))]
+mod bare;
+#[cfg(feature = "std")]
+mod callsite;
+#[cfg(feature = "std")]
+mod capture;
+#[cfg(feature = "std")]
+mod check;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+mod const_eval;
+#[cfg(feature = "std")]
+mod context;
+#[cfg(feature = "std")]
+mod count;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "std")]
+mod directive;
+#[cfg(feature = "std")]
+mod dump;
+#[cfg(feature = "std")]
+mod failing;
+#[cfg(feature = "std")]
+mod future;
+#[cfg(feature = "std")]
+mod group;
+#[cfg(feature = "std")]
+mod hash;
+#[cfg(feature = "std")]
+mod hex;
+#[cfg(feature = "std")]
+mod iter;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "std")]
+mod lanes;
+#[cfg(feature = "std")]
+mod limit;
+#[cfg(feature = "std")]
+mod literal;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "raw_bytes")]
+mod raw;
+#[cfg(feature = "std")]
+mod record;
+#[cfg(feature = "std")]
+mod redact;
+#[cfg(feature = "std")]
+mod run;
+#[cfg(feature = "std")]
+mod scope;
+#[cfg(feature = "serde")]
+mod session;
+#[cfg(feature = "std")]
+pub mod sink;
+#[cfg(feature = "std")]
+mod size;
+#[cfg(feature = "insta")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod summary;
+#[cfg(feature = "std")]
+mod table;
+#[cfg(feature = "std")]
+mod term;
+#[cfg(feature = "std")]
+mod text;
+#[cfg(feature = "std")]
+mod timeline;
+#[cfg(feature = "std")]
+mod timing;
+#[cfg(feature = "trace")]
+mod trace;
+#[cfg(feature = "std")]
+mod truncate;
+#[cfg(feature = "std")]
+mod watch;
+
+#[cfg(not(feature = "std"))]
+pub use bare::set_core_writer;
+#[cfg(feature = "std")]
+pub use callsite::{reset_callsite_filter, set_callsite_filter, set_module_filter, Callsite};
+#[cfg(feature = "std")]
+pub use capture::{capture, Capture};
+#[cfg(feature = "std")]
+pub use config::{
+    deterministic, enabled, flag_non_finite, fold_markers, indent_scopes, max_output,
+    redact_addresses, set_deterministic, set_enabled, set_flag_non_finite, set_fold_markers,
+    set_indent_scopes, set_max_output, set_redact_addresses, set_show_run_id, set_show_thread,
+    set_timestamps, show_run_id, show_thread, timestamps, FoldMarkers, MaxOutput, Timestamps,
+};
+#[cfg(feature = "std")]
+pub use context::{clear_context, context, set_context, ContextGuard};
+#[cfg(feature = "std")]
+pub use count::{report_counts, report_counts_at_exit};
+#[cfg(all(feature = "signal", unix))]
+pub use dump::dump_on_sigusr1;
+#[cfg(feature = "std")]
+pub use dump::{dump_state, install_panic_hook};
+#[cfg(feature = "std")]
+pub use failing::report_failing_input;
+#[cfg(feature = "rayon")]
+pub use par::ParallelIteratorDbg;
+#[cfg(feature = "std")]
+pub use record::Record;
+#[cfg(feature = "std")]
+pub use run::run_id;
+#[cfg(feature = "serde")]
+pub use session::{replay, set_session_file};
+#[cfg(feature = "std")]
+pub use sink::{set_sink, set_writer, to_file, Sink};
+#[cfg(feature = "std")]
+pub use stats::{report_stats, report_stats_at_exit};
+#[cfg(feature = "std")]
+pub use summary::{set_slice_edge, slice_edge};
+#[cfg(feature = "std")]
+pub use table::flush_table;
+#[cfg(feature = "std")]
+pub use term::{terminal, Terminal};
+#[cfg(feature = "std")]
+pub use timeline::dump_timeline;
+#[cfg(feature = "std")]
+pub use watch::dump_all;
+
+#[cfg(feature = "trace")]
+pub use dbgonly_macros::trace;
+
+/// An alias for [`dbgonly!`] under the name of `std::dbg!`.
+///
+/// Importing it shadows the standard macro, so every existing `dbg!` call in
+/// a module becomes debug-only without renaming them:
+///
+/// ```rust
+/// use dbgonly::dbg;
+/// let a = 2;
+/// let b = dbg!(a * 2) + 1;
+/// assert_eq!(b, 5);
+/// ```
+///
+/// This requires the `dbg` feature.
+#[cfg(feature = "dbg")]
+pub use crate::dbgonly as dbg;
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "std")]
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    pub use crate::bare::{emit, Record};
+
+    #[cfg(feature = "std")]
+    pub use crate::callsite::callsite_at;
+    #[cfg(feature = "std")]
+    pub use crate::check::{
+        assert_failed, backtrace, expect_failed, short_backtrace, Expect, FiniteProbe, Float,
+        HasFloats, NoFloats,
+    };
+    #[cfg(feature = "std")]
+    pub use crate::const_eval::const_probe;
+    #[cfg(feature = "std")]
+    pub use crate::count::Counter;
+    #[cfg(feature = "std")]
+    pub use crate::diff::debug_diff;
+    #[cfg(feature = "std")]
+    pub use crate::future::DbgFuture;
+    #[cfg(feature = "std")]
+    pub use crate::group::Group;
+    #[cfg(feature = "std")]
+    pub use crate::hash::{crc32, fingerprint};
+    #[cfg(feature = "std")]
+    pub use crate::hex::{Hex, HexValue};
+    #[cfg(feature = "std")]
+    pub use crate::iter::DbgIter;
+    #[cfg(feature = "serde")]
+    pub use crate::json::to_json;
+    #[cfg(feature = "std")]
+    pub use crate::lanes::{lanes, lanes_as, Lane, Vector};
+    #[cfg(feature = "std")]
+    pub use crate::limit::{LastValue, RateLimit, Skipped};
+    #[cfg(feature = "std")]
+    pub use crate::literal::to_literal;
+    #[cfg(feature = "raw_bytes")]
+    pub use crate::raw::{hexdump, raw_bytes, raw_bytes_unchecked};
+    #[cfg(feature = "std")]
+    pub use crate::scope::Scope;
+    #[cfg(feature = "serde")]
+    pub use crate::session::record_value;
+    #[cfg(feature = "std")]
+    pub use crate::size::{ContainerSize, Footprint, PlainSize, SizeOf};
+    #[cfg(feature = "std")]
+    pub use crate::stats::Stats;
+    #[cfg(feature = "std")]
+    pub use crate::summary::{Collection, MapSummary, SliceSummary};
+    #[cfg(feature = "std")]
+    pub use crate::table::{add_row, items_table};
+    #[cfg(feature = "std")]
+    pub use crate::text::{chars_table, unified_diff, Visible};
+    #[cfg(feature = "std")]
+    pub use crate::timeline::record_event;
+    #[cfg(feature = "std")]
+    pub use crate::timing::emit_elapsed;
+    #[cfg(feature = "trace")]
+    pub use crate::trace::Trace;
+    #[cfg(feature = "std")]
+    pub use crate::watch::{dump_watch, set_watch};
+    #[cfg(feature = "insta")]
+    pub use insta;
+    #[cfg(feature = "std")]
+    pub use std::time::Instant;
+
+    /// Returns the name of the function that a marker function named
+    /// `__dbgonly_here` is defined in, relative to its module.
+    pub fn function_name(marker: &'static str, module_path: &str) -> &'static str {
+        let mut name = marker.strip_suffix("::__dbgonly_here").unwrap_or(marker);
+        while let Some(outer) = name.strip_suffix("::{{closure}}") {
+            name = outer;
+        }
+        name.strip_prefix(module_path)
+            .and_then(|name| name.strip_prefix("::"))
+            .unwrap_or(name)
+    }
+
+    /// The values of a multi-value `dbgonly!`, which are printed as one
+    /// record so that they stay together.
+    pub struct Values<'a>(pub &'a [(&'static str, &'a dyn core::fmt::Debug)]);
+
+    impl core::fmt::Display for Values<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            for (i, (expr, value)) in self.0.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{} = {:#?}", expr, value)?;
+            }
+            Ok(())
+        }
+    }
+
+    // NOTE: Every enabled call site only builds the `fmt::Arguments` and
+    // calls this function. Keeping it out of line and cold keeps the context
+    // lookup, locking and writing from being inlined into hundreds of call
+    // sites.
+    #[cfg(feature = "std")]
+    #[cold]
+    #[inline(never)]
+    pub fn emit(
+        callsite: &'static Callsite,
+        value: Option<fmt::Arguments<'_>>,
+        fields: &[(&'static str, &dyn fmt::Debug)],
+    ) {
+        emit_flagged(callsite, value, fields, false);
+    }
+
+    // Like `emit`, for values that were checked for NaN and infinities.
+    #[cfg(feature = "std")]
+    #[cold]
+    #[inline(never)]
+    pub fn emit_flagged(
+        callsite: &'static Callsite,
+        value: Option<fmt::Arguments<'_>>,
+        fields: &[(&'static str, &dyn fmt::Debug)],
+        non_finite: bool,
+    ) {
+        let context = context::current();
+        sink::emit(
+            &Record::new(
+                callsite.file(),
+                callsite.line(),
+                callsite.module_path(),
+                callsite.expr(),
+                value,
+                fields,
+                context.as_deref(),
+            )
+            .with_non_finite(non_finite),
+        );
+    }
+}
+
+// Expands to whether the crate invoking a macro is silenced, which is checked
+// with the cfg options of that crate rather than of this one. Crates built
+// with `--cfg dbgonly_disable` are silenced, and so are crates built without
+// `debug_assertions` unless the `force-enable` feature is on.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(dbgonly_forced)]
+macro_rules! __silenced {
+    () => {{
+        // `dbgonly_disable` is not declared by the invoking crate.
+        #[allow(unexpected_cfgs)]
+        const SILENCED: bool = cfg!(dbgonly_disable);
+        SILENCED
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(dbgonly_forced))]
+macro_rules! __silenced {
+    () => {{
+        #[allow(unexpected_cfgs)]
+        const SILENCED: bool = cfg!(any(dbgonly_disable, not(debug_assertions)));
+        SILENCED
+    }};
+}
+
+// Like `__silenced!`, for `dbgtest!`, which is active in the tests of the
+// invoking crate regardless of `debug_assertions`.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(dbgonly_force_disable)]
+macro_rules! __test_silenced {
+    () => {
+        true
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(dbgonly_force_disable))]
+macro_rules! __test_silenced {
+    () => {{
+        #[allow(unexpected_cfgs)]
+        const SILENCED: bool = cfg!(dbgonly_disable);
+        SILENCED
+    }};
+}
+
+// Expands to whether any of the values is a float or a container of floats
+// that holds a NaN or infinity, if these are flagged.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "std")]
+macro_rules! __non_finite {
+    ($($val:expr),+ $(,)?) => {
+        $crate::flag_non_finite() && {
+            #[allow(unused_imports)]
+            use $crate::__private::{HasFloats as _, NoFloats as _};
+            false $(|| (&$crate::__private::FiniteProbe($val)).non_finite())+
+        }
+    };
+}
+
+// Expands to the static `Callsite` of the invoking macro and, if it is
+// enabled, a call to `emit`. The value and fields are only evaluated when the
+// callsite is enabled, and so is whether the value is non-finite, if given.
+// Unless given, whether the callsite is silenced is decided by `__silenced!`.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "std")]
+macro_rules! __emit {
+    (@silenced $silenced:expr; $expr:expr, $value:expr, $fields:expr $(,)?) => {{
+        static CALLSITE: $crate::Callsite =
+            $crate::Callsite::new(file!(), line!(), module_path!(), $expr).silenced_if($silenced);
+        if CALLSITE.is_enabled() {
+            $crate::__private::emit(&CALLSITE, $value, $fields);
+        }
+    }};
+    ($expr:expr, $value:expr, $fields:expr $(,)?) => {
+        $crate::__emit!(@silenced $crate::__silenced!(); $expr, $value, $fields)
+    };
+    ($expr:expr, $value:expr, $fields:expr, $non_finite:expr $(,)?) => {{
+        static CALLSITE: $crate::Callsite =
+            $crate::Callsite::new(file!(), line!(), module_path!(), $expr)
+                .silenced_if($crate::__silenced!());
+        if CALLSITE.is_enabled() {
+            $crate::__private::emit_flagged(&CALLSITE, $value, $fields, $non_finite);
+        }
+    }};
+}
+
+// Like `__emit!`, but for a location that may only be known at runtime.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "std")]
+macro_rules! __emit_at {
+    ($file:expr, $line:expr, $expr:expr, $value:expr, $fields:expr $(,)?) => {{
+        if !$crate::__silenced!() {
+            let callsite = $crate::__private::callsite_at($file, $line, module_path!(), $expr);
+            if callsite.is_enabled() {
+                $crate::__private::emit(callsite, $value, $fields);
+            }
+        }
+    }};
+    ($file:expr, $line:expr, $expr:expr, $value:expr, $fields:expr, $non_finite:expr $(,)?) => {{
+        if !$crate::__silenced!() {
+            let callsite = $crate::__private::callsite_at($file, $line, module_path!(), $expr);
+            if callsite.is_enabled() {
+                $crate::__private::emit_flagged(callsite, $value, $fields, $non_finite);
+            }
+        }
+    }};
+}
+
+// Without the standard library, records go to the writer set with
+// `set_core_writer`, or to `defmt` with the `defmt` feature. Non-finite values
+// are not flagged.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "std"))]
+macro_rules! __emit {
+    (@silenced $silenced:expr; $expr:expr, $value:expr, $fields:expr $(,)?) => {
+        $crate::__emit_at!(@silenced $silenced; file!(), line!(), $expr, $value, $fields)
+    };
+    ($expr:expr, $value:expr, $fields:expr $(,)?) => {
+        $crate::__emit_at!(file!(), line!(), $expr, $value, $fields)
+    };
+    ($expr:expr, $value:expr, $fields:expr, $non_finite:expr $(,)?) => {
+        $crate::__emit_at!(file!(), line!(), $expr, $value, $fields)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(all(not(feature = "std"), not(feature = "defmt")))]
+macro_rules! __emit_at {
+    ($file:expr, $line:expr, $expr:expr, $value:expr, $fields:expr, $non_finite:expr $(,)?) => {
+        $crate::__emit_at!($file, $line, $expr, $value, $fields)
+    };
+    ($file:expr, $line:expr, $expr:expr, $value:expr, $fields:expr $(,)?) => {
+        $crate::__emit_at!(@silenced $crate::__silenced!(); $file, $line, $expr, $value, $fields)
+    };
+    (
+        @silenced $silenced:expr;
+        $file:expr, $line:expr, $expr:expr, $value:expr, $fields:expr $(,)?
+    ) => {
+        if !$silenced {
+            $crate::__private::emit(&$crate::__private::Record {
+                file: $file,
+                line: $line,
+                expr: $expr,
+                value: $value,
+                fields: $fields,
+            })
+        }
+    };
+}
+
+// NOTE: The `defmt` macros refer to the `defmt` crate by name, so the invoking
+// crate has to depend on it directly.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(all(not(feature = "std"), feature = "defmt"))]
+macro_rules! __emit_at {
+    ($file:expr, $line:expr, $expr:expr, $value:expr, $fields:expr, $non_finite:expr $(,)?) => {
+        $crate::__emit_at!($file, $line, $expr, $value, $fields)
+    };
+    ($file:expr, $line:expr, $expr:expr, $value:expr, $fields:expr $(,)?) => {
+        $crate::__emit_at!(@silenced $crate::__silenced!(); $file, $line, $expr, $value, $fields)
+    };
+    (
+        @silenced $silenced:expr;
+        $file:expr, $line:expr, $expr:expr, $value:expr, $fields:expr $(,)?
+    ) => {
+        if !$silenced {
+            ::defmt::debug!(
+                "{}",
+                ::defmt::Display2Format(&$crate::__private::Record {
+                    file: $file,
+                    line: $line,
+                    expr: $expr,
+                    value: $value,
+                    fields: $fields,
+                })
+            )
+        }
+    };
+}
+
 /// Prints and returns the value of a given expression for quick and dirty
 /// debugging. This version of the macro will print nothing and be optmized
 /// out in release builds.
@@ -20,7 +502,8 @@
 /// The macro works by using the `Debug` implementation of the type of
 /// the given expression to print the value to [stderr] along with the
 /// source location of the macro invocation as well as the source code
-/// of the expression.
+/// of the expression. The output can be redirected elsewhere by installing
+/// a different [`Sink`] with [`set_sink`].
 ///
 /// Invoking the macro on an expression moves and takes ownership of it
 /// before returning the evaluated expression unchanged. If the type
@@ -28,7 +511,34 @@
 /// to give up ownership, you can instead borrow with `dbgonly!(&expr)`
 /// for some expression `expr`.
 ///
-/// The `dbgonly!` macro is optimized out in release builds.
+/// The `dbgonly!` macro is optimized out in release builds. It is also
+/// optimized out when compiling with `--cfg fuzzing`, as `cargo fuzz` does, so
+/// that leftover calls don't slow down the fuzzer or clutter its output.
+///
+/// This applies to all macros of this crate. Their gating can be overridden
+/// from `Cargo.toml`: the `force-enable` feature makes them print in builds
+/// without `debug_assertions`, such as optimized builds for soak tests, and
+/// the `force-disable` feature silences them in every build, including the
+/// test builds that [`dbgtest!`] prints in.
+///
+/// Whether a call prints is also decided by the crate that contains it, so
+/// leftover calls in dependencies can be silenced without touching them.
+/// Calls in crates built without `debug_assertions` print nothing unless
+/// `force-enable` is on, which lets a profile override keep only your own
+/// crates chatty:
+///
+/// ```toml
+/// [profile.dev.package."*"]
+/// debug-assertions = false
+///
+/// [profile.dev.package.dbgonly]
+/// debug-assertions = true
+/// ```
+///
+/// Calls in crates built with `--cfg dbgonly_disable` print nothing in any
+/// case, which is useful with build systems that set flags per crate.
+/// Silenced calls still evaluate and return their arguments, and checks such
+/// as [`dbg_assert!`](crate::dbg_assert) still run.
 ///
 /// Note that the macro is intended as a debugging tool and therefore you
 /// should avoid having uses of it in version control for long periods
@@ -43,7 +553,7 @@
 ///
 /// # Panics
 ///
-/// Panics if writing to `io::stderr` fails.
+/// Panics if writing to `io::stderr` fails while the default sink is in use.
 ///
 /// # Further examples
 ///
@@ -108,11 +618,15 @@
 /// let _ = dbgonly!(a); // <-- `a` is moved again; error!
 /// ```
 ///
+/// To only borrow the value, use [`dbg_ref!`] or write `dbgonly!(&a)`.
+///
 /// You can also use `dbgonly!()` without a value to just print the
 /// file and line whenever it's reached.
 ///
 /// Finally, if you want to `dbgonly!(..)` multiple values, it will treat them as
-/// a tuple (and return it, too):
+/// a tuple (and return it, too). They are printed as a single record, such as
+/// `[src/main.rs:2] 1usize = 1, 2u32 = 2`, so output from other threads cannot
+/// end up between them:
 ///
 /// ```
 /// use dbgonly::dbgonly;
@@ -129,45 +643,656 @@
 /// assert_eq!((1,), dbgonly!((1u32,))); // 1-tuple
 /// ```
 ///
+/// Additional key-value fields can be attached to a single value after a
+/// semicolon. They are printed after the value and handed to the [`Sink`]
+/// separately, so structured sinks can store them as proper fields. The
+/// field expressions are only evaluated when the value is printed:
+///
+/// ```
+/// use dbgonly::dbgonly;
+/// let (id, n) = (42, 3);
+/// let user = dbgonly!("alice"; user_id = id, attempt = n);
+/// //         ^-- prints: [src/main.rs:3] "alice" = "alice" user_id=42 attempt=3
+/// assert_eq!(user, "alice");
+/// ```
+///
+/// Macros and functions that wrap `dbgonly!` can attribute the output to
+/// their own caller instead of the line inside the wrapper. `@loc` takes the
+/// file and line explicitly, and `@caller` takes them from the caller of the
+/// enclosing `#[track_caller]` function:
+///
+/// ```
+/// use dbgonly::dbgonly;
+/// macro_rules! probe {
+///     ($val:expr) => {
+///         dbgonly!(@loc file!(), line!(); $val)
+///     };
+/// }
+///
+/// #[track_caller]
+/// fn checked(n: i32) -> i32 {
+///     dbgonly!(@caller; n)
+/// }
+///
+/// let a = probe!(1 + 1);
+/// //      ^-- prints: [src/main.rs:12] 1 + 1 = 2
+/// let b = checked(a);
+/// //      ^-- prints: [src/main.rs:14] n = 2
+/// assert_eq!(b, 2);
+/// ```
+///
+/// The file must be a `&'static str` and the line a `u32`. Since the location
+/// may only be known at runtime, its callsite is looked up in a global table
+/// on every call, which makes these forms slower than the others.
+///
 /// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
 /// [`debug!`]: https://docs.rs/log/*/log/macro.debug.html
 /// [`log`]: https://crates.io/crates/log
 #[macro_export]
-#[cfg(debug_assertions)]
+#[cfg(dbgonly_active)]
 macro_rules! dbgonly {
-    // NOTE: We cannot use `concat!` to make a static string as a format argument
-    // of `eprintln!` because `file!` could contain a `{` or
-    // `$val` expression could be a block (`{ .. }`), in which case the `eprintln!`
-    // will be malformed.
+    // NOTE: The location and expression are passed to the sink as separate
+    // fields rather than being baked into the format string with `concat!`
+    // because `file!` could contain a `{` or `$val` expression could be a block
+    // (`{ .. }`), in which case the format string would be malformed.
     () => {
-        eprintln!("[{}:{}]", file!(), line!())
+        $crate::__emit!(
+            ::core::option::Option::None,
+            ::core::option::Option::None, &[])
+    };
+    (@loc $file:expr, $line:expr; $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit_at!(
+                    $file,
+                    $line,
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{:#?}", &tmp)),
+                    &[],
+                    $crate::__non_finite!(&tmp));
+                tmp
+            }
+        }
+    };
+    (@caller; $val:expr $(,)?) => {
+        $crate::dbgonly!(
+            @loc ::core::panic::Location::caller().file(),
+            ::core::panic::Location::caller().line();
+            $val
+        )
+    };
+    // Multiple values are bound one at a time, since every expansion gets its
+    // own `tmp`, and then printed as a single record so that they are not
+    // interleaved with the output of other threads.
+    (@values [$(($tmp:ident, $done:expr))*] $val:expr, $($rest:expr,)*) => {
+        match $val {
+            tmp => $crate::dbgonly!(@values [$(($tmp, $done))* (tmp, $val)] $($rest,)*),
+        }
+    };
+    (@values [$(($tmp:ident, $done:expr))+]) => {{
+        $crate::__emit!(
+            ::core::option::Option::None,
+            ::core::option::Option::Some(format_args!(
+                "{}",
+                $crate::__private::Values(&[
+                    $((stringify!($done), &$tmp as &dyn ::core::fmt::Debug)),+
+                ])
+            )),
+            &[],
+            $crate::__non_finite!($(&$tmp),+));
+        ($($tmp),+,)
+    }};
+    ($val:expr; $($key:ident = $field:expr),+ $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{:#?}", &tmp)),
+                    &[$((stringify!($key), &$field as &dyn ::core::fmt::Debug)),+],
+                    $crate::__non_finite!(&tmp));
+                tmp
+            }
+        }
     };
     ($val:expr $(,)?) => {
         // Use of `match` here is intentional because it affects the lifetimes
         // of temporaries - https://stackoverflow.com/a/48732525/1063961
         match $val {
             tmp => {
-                eprintln!("[{}:{}] {} = {:#?}",
-                    file!(), line!(), stringify!($val), &tmp);
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{:#?}", &tmp)),
+                    &[],
+                    $crate::__non_finite!(&tmp));
                 tmp
             }
         }
     };
     ($($val:expr),+ $(,)?) => {
-        ($(dbgonly!($val)),+,)
+        $crate::dbgonly!(@values [] $($val,)+)
     };
 }
 
 #[macro_export]
-#[cfg(not(debug_assertions))]
+#[cfg(not(dbgonly_active))]
 macro_rules! dbgonly {
     () => {};
+    (@loc $file:expr, $line:expr; $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _: (&'static str, u32) = ($file, $line);
+                }
+                tmp
+            }
+        }
+    };
+    (@caller; $val:expr $(,)?) => {
+        match $val {
+            tmp => tmp
+        }
+    };
+    ($val:expr; $($key:ident = $field:expr),+ $(,)?) => {
+        match $val {
+            tmp => {
+                // Keep the fields type-checked and their variables used
+                // without evaluating them.
+                if false {
+                    $(let _ = &$field;)+
+                }
+                tmp
+            }
+        }
+    };
     ($val:expr $(,)?) => {
         match $val {
             tmp => tmp
         }
     };
     ($($val:expr),+ $(,)?) => {
-        ($(dbgonly!($val)),+,)
+        ($($crate::dbgonly!($val)),+,)
+    };
+}
+
+/// Prints and returns the value of a given expression on a single line. This
+/// version of the macro will print nothing and be optimized out in release
+/// builds.
+///
+/// This is [`dbgonly!`] with `{:?}` instead of `{:#?}`, so a large struct
+/// takes one line instead of hundreds. That keeps output greppable and loops
+/// readable:
+///
+/// ```
+/// use dbgonly::dbg_compact;
+/// #[derive(Debug)]
+/// struct Point { x: i32, y: i32 }
+///
+/// let p = dbg_compact!(Point { x: 1, y: -2 });
+/// //      ^-- prints: [src/main.rs:5] Point { x: 1, y: -2 } = Point { x: 1, y: -2 }
+/// assert_eq!(p.y, -2);
+/// ```
+///
+/// It accepts multiple values and fields like [`dbgonly!`].
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_compact {
+    ($val:expr; $($key:ident = $field:expr),+ $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{:?}", &tmp)),
+                    &[$((stringify!($key), &$field as &dyn ::core::fmt::Debug)),+]);
+                tmp
+            }
+        }
+    };
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{:?}", &tmp)), &[]);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_compact!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_compact {
+    ($val:expr; $($key:ident = $field:expr),+ $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    $(let _ = &$field;)+
+                }
+                tmp
+            }
+        }
+    };
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_compact!($val)),+,)
+    };
+}
+
+/// Prints and returns the value of a given expression with a label. This
+/// version of the macro will print nothing and be optimized out in release
+/// builds.
+///
+/// The label is a string literal that is printed before the expression, which
+/// tells apart the same expression debugged in several places:
+///
+/// ```
+/// use dbgonly::dbg_msg;
+/// let mut total = 40;
+/// dbg_msg!("before merge step", total);
+/// //  ^-- prints: [src/main.rs:3] before merge step: total = 40
+/// total += 2;
+/// let total = dbg_msg!("after merge step", total);
+/// //          ^-- prints: [src/main.rs:6] after merge step: total = 42
+/// # assert_eq!(total, 42);
+/// ```
+///
+/// Without a value, only the label is printed. With multiple values, each is
+/// printed with the label and they are returned as a tuple.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_msg {
+    ($msg:literal $(,)?) => {
+        $crate::__emit!(
+            ::core::option::Option::Some($msg),
+            ::core::option::Option::None, &[])
+    };
+    ($msg:literal, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(concat!($msg, ": ", stringify!($val))),
+                    ::core::option::Option::Some(format_args!("{:#?}", &tmp)), &[]);
+                tmp
+            }
+        }
+    };
+    ($msg:literal, $($val:expr),+ $(,)?) => {
+        ($($crate::dbg_msg!($msg, $val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_msg {
+    ($msg:literal $(,)?) => {
+        if false {
+            let _: &str = $msg;
+        }
+    };
+    ($msg:literal, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _: &str = $msg;
+                }
+                tmp
+            }
+        }
+    };
+    ($msg:literal, $($val:expr),+ $(,)?) => {
+        ($($crate::dbg_msg!($msg, $val)),+,)
+    };
+}
+
+/// Returns the value of a given expression and prints it only if a condition
+/// holds. This version of the macro will evaluate only the expression and
+/// print nothing in release builds.
+///
+/// This narrows the output down to the interesting cases without wrapping the
+/// call site in an `if` block, which would change the scope of the value:
+///
+/// ```
+/// use dbgonly::dbg_if;
+/// for step in 0..1000_u32 {
+///     let energy = dbg_if!(step % 500 == 0, step * step);
+///     //           ^-- prints: [src/main.rs:3] step * step = 0
+///     //                       [src/main.rs:3] step * step = 250000
+///     # let _ = energy;
+/// }
+/// ```
+///
+/// The condition is evaluated after the expression, and not at all in
+/// release builds.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_if {
+    ($cond:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if $cond {
+                    $crate::__emit!(
+                        ::core::option::Option::Some(stringify!($val)),
+                        ::core::option::Option::Some(format_args!("{:#?}", &tmp)),
+                        &[]
+                    );
+                }
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_if {
+    ($cond:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _: bool = $cond;
+                }
+                tmp
+            }
+        }
+    };
+}
+
+/// Prints the value of a given expression and returns a reference to it
+/// instead of the value. This version of the macro will print nothing and be
+/// optimized out in release builds.
+///
+/// Unlike [`dbgonly!`], the expression is borrowed rather than moved, so a
+/// value that is not `Copy` can be inspected and used afterwards without
+/// writing `&` at the call site:
+///
+/// ```
+/// use dbgonly::dbg_ref;
+/// #[derive(Debug)]
+/// struct NoCopy(usize);
+///
+/// let a = NoCopy(42);
+/// dbg_ref!(a); // <-- `a` is only borrowed here.
+/// //  ^-- prints: [src/main.rs:6] a = NoCopy(
+/// //                  42,
+/// //              )
+/// let n = dbg_ref!(a).0;
+/// assert_eq!(n, 42);
+/// let b = a; // <-- `a` can still be moved.
+/// # let _ = b;
+/// ```
+///
+/// The reference borrows the value like `&expr` would, so for a temporary it
+/// is only valid until the end of the statement. Like [`dbgonly!`], multiple
+/// values are treated as a tuple, of references in this case.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_ref {
+    ($val:expr $(,)?) => {
+        match &$val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{:#?}", tmp)),
+                    &[],
+                    $crate::__non_finite!(tmp));
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_ref!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_ref {
+    ($val:expr $(,)?) => {
+        match &$val {
+            tmp => tmp
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_ref!($val)),+,)
+    };
+}
+
+/// Prints and returns the value of a given expression using its `Display`
+/// implementation. This version of the macro will print nothing and be
+/// optimized out in release builds.
+///
+/// This is [`dbgonly!`] with `{}` instead of `{:#?}`, for values that
+/// implement `Display` but not `Debug`, or whose `Display` output is simply
+/// more useful, like many error types:
+///
+/// ```
+/// use dbgonly::dbg_display;
+/// let err = "12a".parse::<u8>().unwrap_err();
+/// let err = dbg_display!(err);
+/// //        ^-- prints: [src/main.rs:3] err = invalid digit found in string
+/// # let _ = err;
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_display {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{}", &tmp)), &[]);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_display!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_display {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _ = format_args!("{}", &tmp);
+                }
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_display!($val)),+,)
+    };
+}
+
+/// Prints and returns the value of a given expression along with the name of
+/// its type. This version of the macro will print nothing and be optimized
+/// out in release builds.
+///
+/// In generic code and long chains of combinators, the concrete type is often
+/// what is being looked for:
+///
+/// ```
+/// use dbgonly::dbg_type;
+/// let evens = dbg_type!((1..=4_u32).filter(|n| n % 2 == 0));
+/// //          ^-- prints: [src/main.rs:2] (1..=4_u32).filter(|n| n % 2 == 0): core::iter::adapters::filter::Filter<core::ops::range::RangeInclusive<u32>, main::{{closure}}> = Filter { iter: 1..=4 }
+/// let v = dbg_type!(evens.collect::<Vec<_>>());
+/// //      ^-- prints: [src/main.rs:4] evens.collect::<Vec<_>>(): alloc::vec::Vec<u32> = [2, 4]
+/// # assert_eq!(v, [2, 4]);
+/// ```
+///
+/// The name comes from [`core::any::type_name`], so its exact form is not
+/// guaranteed to be stable between compiler versions.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_type {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::None,
+                    ::core::option::Option::Some(format_args!(
+                        "{}: {} = {:#?}",
+                        stringify!($val),
+                        ::core::any::type_name_of_val(&tmp),
+                        &tmp
+                    )),
+                    &[]);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_type!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_type {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_type!($val)),+,)
+    };
+}
+
+/// Prints and returns the value of a given expression along with the name of
+/// the function it is written in. This version of the macro will print
+/// nothing and be optimized out in release builds.
+///
+/// It is meant for the tail of a function, where which function produced a
+/// value matters more than the expression it was computed with:
+///
+/// ```
+/// use dbgonly::dbg_ret;
+/// fn parse_port(s: &str) -> Result<u16, std::num::ParseIntError> {
+///     dbg_ret!(s.trim().parse())
+///     //  ^-- prints: [src/main.rs:3] parse_port -> Ok(8080)
+/// }
+/// # assert_eq!(parse_port(" 8080 "), Ok(8080));
+/// ```
+///
+/// Methods are named with their type, such as `Header::parse`, and closures
+/// with the function they are defined in.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_ret {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                fn __dbgonly_here() {}
+                $crate::__emit!(
+                    ::core::option::Option::None,
+                    ::core::option::Option::Some(format_args!(
+                        "{} -> {:#?}",
+                        $crate::__private::function_name(
+                            ::core::any::type_name_of_val(&__dbgonly_here),
+                            module_path!(),
+                        ),
+                        &tmp
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_ret {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+}
+
+/// Prints and returns the value of a given expression, but only when the
+/// invoking crate is compiled with `cfg(test)`.
+///
+/// Unlike [`dbgonly!`], which follows `debug_assertions`, this macro is
+/// active exactly while running the crate's own unit tests, in both debug
+/// and release profiles (`cargo test --release`). Everywhere else, including
+/// regular debug builds of the library, it passes the value through
+/// unchanged without printing anything. Like the other macros, it is
+/// silenced by the `force-disable` feature and in crates built with
+/// `--cfg dbgonly_disable`.
+///
+/// It accepts the same forms as [`dbgonly!`]:
+///
+/// ```
+/// use dbgonly::dbgtest;
+/// // Doctests are not compiled with `cfg(test)`, so nothing is printed here.
+/// assert_eq!(dbgtest!(1 + 1), 2);
+/// assert_eq!(dbgtest!(1usize, 2u32), (1, 2));
+/// dbgtest!();
+/// ```
+#[macro_export]
+macro_rules! dbgtest {
+    // NOTE: The `cfg` attributes on the match arms are evaluated in the
+    // invoking crate, which is what makes `cfg(test)` refer to its tests.
+    () => {
+        match () {
+            #[cfg(test)]
+            () => $crate::__emit!(
+                @silenced $crate::__test_silenced!();
+                ::core::option::Option::None,
+                ::core::option::Option::None, &[]),
+            #[cfg(not(test))]
+            () => (),
+        }
+    };
+    ($val:expr; $($key:ident = $field:expr),+ $(,)?) => {
+        match $val {
+            #[cfg(test)]
+            tmp => {
+                $crate::__emit!(
+                    @silenced $crate::__test_silenced!();
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{:#?}", &tmp)),
+                    &[$((stringify!($key), &$field as &dyn ::core::fmt::Debug)),+]);
+                tmp
+            }
+            #[cfg(not(test))]
+            tmp => {
+                if false {
+                    $(let _ = &$field;)+
+                }
+                tmp
+            }
+        }
+    };
+    ($val:expr $(,)?) => {
+        match $val {
+            #[cfg(test)]
+            tmp => {
+                $crate::__emit!(
+                    @silenced $crate::__test_silenced!();
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{:#?}", &tmp)), &[]);
+                tmp
+            }
+            #[cfg(not(test))]
+            tmp => tmp,
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbgtest!($val)),+,)
     };
 }
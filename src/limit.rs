@@ -0,0 +1,337 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::Instant;
+
+use crate::Callsite;
+
+/// Prints and returns the value of a given expression, but only the first
+/// time this call site is reached. This version of the macro will print
+/// nothing and be optimized out in release builds.
+///
+/// The value is still evaluated and returned every time, so the macro can
+/// stay inside loops and recursive functions without flooding the output:
+///
+/// ```rust
+/// use dbgonly::dbg_once;
+/// let mut total = 0;
+/// for n in 1..=100 {
+///     total += dbg_once!(n * n);
+///     //       ^-- prints once: [src/main.rs:4] n * n = 1
+/// }
+/// # assert_eq!(total, 338_350);
+/// ```
+///
+/// Each call site counts as reached once it has printed, so a call site
+/// that is disabled with [`set_callsite_filter`](crate::set_callsite_filter)
+/// or [`set_enabled`](crate::set_enabled) can still print later.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_once {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                static PRINTED: ::core::sync::atomic::AtomicBool =
+                    ::core::sync::atomic::AtomicBool::new(false);
+                if !PRINTED.load(::core::sync::atomic::Ordering::Relaxed) {
+                    static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+                        file!(),
+                        line!(),
+                        module_path!(),
+                        ::core::option::Option::Some(stringify!($val)),
+                    ).silenced_if($crate::__silenced!());
+                    if CALLSITE.is_enabled()
+                        && !PRINTED.swap(true, ::core::sync::atomic::Ordering::Relaxed)
+                    {
+                        $crate::__private::emit(
+                            &CALLSITE,
+                            ::core::option::Option::Some(format_args!("{:#?}", &tmp)),
+                            &[],
+                        );
+                    }
+                }
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_once!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_once {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_once!($val)),+,)
+    };
+}
+
+/// Prints and returns the value of a given expression, but only on every
+/// `n`th time this call site is reached, starting with the first. This
+/// version of the macro will print nothing and be optimized out in release
+/// builds.
+///
+/// The value is still evaluated and returned every time. The number of the
+/// hit is printed after the value, which keeps hot loops and per-frame code
+/// readable:
+///
+/// ```rust
+/// use dbgonly::dbg_every;
+/// for frame in 0..300 {
+///     let dt = dbg_every!(100, 1.0 / 60.0 + f64::from(frame % 7) * 1e-4);
+///     //       ^-- prints: [src/main.rs:3] 1.0 / 60.0 + f64::from(frame % 7) * 1e-4 = 0.016666666666666666 (hit 1)
+///     //                   [src/main.rs:3] 1.0 / 60.0 + f64::from(frame % 7) * 1e-4 = 0.016866666666666665 (hit 101)
+///     //                   [src/main.rs:3] 1.0 / 60.0 + f64::from(frame % 7) * 1e-4 = 0.016766666666666666 (hit 201)
+///     # let _ = dt;
+/// }
+/// ```
+///
+/// Hits are counted whether or not the call site is enabled.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_every {
+    ($n:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                static HITS: ::core::sync::atomic::AtomicUsize =
+                    ::core::sync::atomic::AtomicUsize::new(0);
+                let hit = HITS.fetch_add(1, ::core::sync::atomic::Ordering::Relaxed);
+                let n: usize = $n;
+                if hit % n.max(1) == 0 {
+                    $crate::__emit!(
+                        ::core::option::Option::Some(stringify!($val)),
+                        ::core::option::Option::Some(format_args!("{:#?} (hit {})", &tmp, hit + 1)),
+                        &[]
+                    );
+                }
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_every {
+    ($n:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _: usize = $n;
+                }
+                tmp
+            }
+        }
+    };
+}
+
+/// Prints and returns the value of a given expression, but at most once per
+/// `ms` milliseconds at this call site. This version of the macro will print
+/// nothing and be optimized out in release builds.
+///
+/// The value is still evaluated and returned every time. The number of hits
+/// that were skipped since the last print is printed after the value:
+///
+/// ```rust
+/// use dbgonly::dbg_every_ms;
+/// for request in 0..10_000 {
+///     let queue_len = dbg_every_ms!(500, request % 17);
+///     //              ^-- prints: [src/main.rs:3] request % 17 = 0
+///     # let _ = queue_len;
+/// }
+/// ```
+///
+/// A later print in the same loop would read, for example,
+/// `[src/main.rs:3] request % 17 = 4 (8124 skipped)`. Hits are only counted
+/// while the call site is enabled, so a disabled call site costs no more
+/// than a check of its state.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_every_ms {
+    ($ms:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+                    file!(),
+                    line!(),
+                    module_path!(),
+                    ::core::option::Option::Some(stringify!($val)),
+                ).silenced_if($crate::__silenced!());
+                static LIMIT: $crate::__private::RateLimit = $crate::__private::RateLimit::new();
+                if CALLSITE.is_enabled() {
+                    if let ::core::option::Option::Some(skipped) = LIMIT.check($ms) {
+                        $crate::__private::emit(
+                            &CALLSITE,
+                            ::core::option::Option::Some(format_args!(
+                                "{:#?}{}",
+                                &tmp,
+                                $crate::__private::Skipped(skipped)
+                            )),
+                            &[],
+                        );
+                    }
+                }
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_every_ms {
+    ($ms:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _: u64 = $ms;
+                }
+                tmp
+            }
+        }
+    };
+}
+
+/// Prints and returns the value of a given expression, but only when its
+/// `Debug` output differs from the last time this call site printed. This
+/// version of the macro will print nothing and be optimized out in release
+/// builds.
+///
+/// The value is still evaluated and returned every time. Watching a value
+/// across many iterations then shows only its transitions, along with the
+/// previous value if it fits on one line:
+///
+/// ```rust
+/// use dbgonly::dbg_changed;
+/// for tick in 0..1000 {
+///     let phase = dbg_changed!(tick / 400);
+///     //          ^-- prints: [src/main.rs:3] tick / 400 = 0
+///     //                      [src/main.rs:3] tick / 400 = 1 (was 0)
+///     //                      [src/main.rs:3] tick / 400 = 2 (was 1)
+///     # let _ = phase;
+/// }
+/// ```
+///
+/// The last output is kept per call site, not per thread.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_changed {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+                    file!(),
+                    line!(),
+                    module_path!(),
+                    ::core::option::Option::Some(stringify!($val)),
+                ).silenced_if($crate::__silenced!());
+                static LAST: $crate::__private::LastValue = $crate::__private::LastValue::new();
+                if CALLSITE.is_enabled() {
+                    LAST.emit_if_changed(&CALLSITE, &tmp);
+                }
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_changed!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_changed {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_changed!($val)),+,)
+    };
+}
+
+/// The state of a [`dbg_changed!`](crate::dbg_changed) call site.
+pub struct LastValue(Mutex<Option<String>>);
+
+impl LastValue {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        LastValue(Mutex::new(None))
+    }
+
+    /// Prints the value if its output differs from the last one.
+    pub fn emit_if_changed(&self, callsite: &'static Callsite, value: &dyn fmt::Debug) {
+        let current = format!("{:#?}", value);
+        let previous = {
+            let mut last = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+            if last.as_deref() == Some(current.as_str()) {
+                return;
+            }
+            last.replace(current.clone())
+        };
+        match previous {
+            Some(previous) if !previous.contains('\n') => crate::__private::emit(
+                callsite,
+                Some(format_args!("{} (was {})", current, previous)),
+                &[],
+            ),
+            _ => crate::__private::emit(callsite, Some(format_args!("{}", current)), &[]),
+        }
+    }
+}
+
+/// The state of a [`dbg_every_ms!`](crate::dbg_every_ms) call site.
+pub struct RateLimit {
+    /// When the call site last printed, in milliseconds since `EPOCH` plus
+    /// one, or zero if it has not printed yet.
+    last: AtomicU64,
+    skipped: AtomicUsize,
+}
+
+impl RateLimit {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        RateLimit {
+            last: AtomicU64::new(0),
+            skipped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of hits skipped since the last print if the call
+    /// site should print now, and counts a skipped hit otherwise.
+    pub fn check(&self, ms: u64) -> Option<usize> {
+        static EPOCH: OnceLock<Instant> = OnceLock::new();
+        let now = EPOCH.get_or_init(Instant::now).elapsed().as_millis() as u64 + 1;
+        let last = self.last.load(Ordering::Relaxed);
+        let due = last == 0 || now.saturating_sub(last) >= ms;
+        if due
+            && self
+                .last
+                .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            Some(self.skipped.swap(0, Ordering::Relaxed))
+        } else {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+pub struct Skipped(pub usize);
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 > 0 {
+            write!(f, " ({} skipped)", self.0)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+/// Prints the value of a given expression as Rust source code and returns the
+/// value. This version of the macro will print nothing and be optimized out in
+/// release builds.
+///
+/// The value's `Debug` output is rewritten on a best-effort basis so that it
+/// can be pasted into a test: lists become `vec![...]`, maps and sets become
+/// `[...].into_iter().collect()`, non-finite floats become constants such as
+/// `f64::NAN`, and string literals in struct fields get an `.into()` so they
+/// fit both `&str` and `String` fields.
+///
+/// ```rust
+/// use dbgonly::dbg_literal;
+/// #[derive(Debug)]
+/// struct Point { name: String, xy: (i32, i32) }
+///
+/// let points = vec![Point { name: "origin".to_string(), xy: (0, 0) }];
+/// let points = dbg_literal!(points);
+/// //           ^-- prints: [src/main.rs:6] points = vec![Point { name: "origin".into(), xy: (0, 0) }]
+/// # assert_eq!(points.len(), 1);
+/// ```
+///
+/// Since only the `Debug` output is available, the result does not always
+/// compile as is: private fields, types whose `Debug` implementation does not
+/// mirror their construction, and values like `Box` or `Rc` that are printed
+/// transparently still need to be fixed up by hand.
+///
+/// Like [`dbgonly!`], multiple values are treated as a tuple.
+///
+/// [`dbgonly!`]: crate::dbgonly
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_literal {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}", $crate::__private::to_literal(&format!("{:?}", &tmp)))),
+                    &[]);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_literal!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_literal {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_literal!($val)),+,)
+    };
+}
+
+/// Rewrites single-line `Debug` output into Rust construction code.
+pub fn to_literal(debug: &str) -> String {
+    let mut parser = Parser {
+        chars: debug.chars().collect(),
+        pos: 0,
+    };
+    let mut out = String::new();
+    while parser.peek().is_some() {
+        let start = parser.pos;
+        out.push_str(&parser.value(false));
+        if parser.pos == start {
+            // Not something we understand; copy it through.
+            out.extend(parser.bump());
+        }
+    }
+    out
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(' ') {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses one value. `in_field` is set for the values of named struct
+    /// fields, where string literals are converted with `.into()`.
+    fn value(&mut self, in_field: bool) -> String {
+        self.skip_spaces();
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                let literal = self.quoted(quote);
+                if in_field && quote == '"' {
+                    format!("{}.into()", literal)
+                } else {
+                    literal
+                }
+            }
+            Some('[') => {
+                self.pos += 1;
+                let (items, _) = self.items(']');
+                format!("vec![{}]", items.join(", "))
+            }
+            Some('(') => {
+                self.pos += 1;
+                let (items, trailing) = self.items(')');
+                if items.len() == 1 && trailing {
+                    format!("({},)", items[0])
+                } else {
+                    format!("({})", items.join(", "))
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                self.collection()
+            }
+            _ => {
+                let word = self.word();
+                if word.is_empty() {
+                    return word;
+                }
+                let word = match word.as_str() {
+                    "NaN" => "f64::NAN".to_owned(),
+                    "inf" => "f64::INFINITY".to_owned(),
+                    "-inf" => "f64::NEG_INFINITY".to_owned(),
+                    _ => word,
+                };
+                let before_space = self.pos;
+                self.skip_spaces();
+                match self.peek() {
+                    Some('(') => {
+                        self.pos += 1;
+                        let (items, _) = self.items(')');
+                        format!("{}({})", word, items.join(", "))
+                    }
+                    Some('{') => {
+                        self.pos += 1;
+                        format!("{} {{ {} }}", word, self.fields().join(", "))
+                    }
+                    _ => {
+                        self.pos = before_space;
+                        word
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parses comma-separated values up to `close`, returning them and
+    /// whether the list had a trailing comma.
+    fn items(&mut self, close: char) -> (Vec<String>, bool) {
+        let mut items = Vec::new();
+        let mut trailing = false;
+        loop {
+            if self.eat(close) || self.peek().is_none() {
+                return (items, trailing);
+            }
+            items.push(self.value(false));
+            trailing = self.eat(',');
+            if !trailing {
+                if self.eat(close) {
+                    return (items, false);
+                }
+                // Unexpected input; skip a character to make progress.
+                self.bump();
+            }
+        }
+    }
+
+    /// Parses the `name: value` fields of a struct up to the closing brace.
+    fn fields(&mut self) -> Vec<String> {
+        let mut fields = Vec::new();
+        loop {
+            if self.eat('}') || self.peek().is_none() {
+                return fields;
+            }
+            let name = self.word();
+            if self.eat(':') {
+                fields.push(format!("{}: {}", name, self.value(true)));
+            } else if name.is_empty() {
+                self.bump();
+            } else {
+                // `..` of a non-exhaustive struct.
+                fields.push(name);
+            }
+            self.eat(',');
+        }
+    }
+
+    /// Parses the entries of a map (`{k: v}`) or set (`{a, b}`) up to the
+    /// closing brace.
+    fn collection(&mut self) -> String {
+        let mut entries = Vec::new();
+        loop {
+            if self.eat('}') || self.peek().is_none() {
+                break;
+            }
+            let key = self.value(false);
+            if self.eat(':') {
+                entries.push(format!("({}, {})", key, self.value(false)));
+            } else {
+                entries.push(key);
+            }
+            if !self.eat(',') {
+                if self.eat('}') {
+                    break;
+                }
+                self.bump();
+            }
+        }
+        if entries.is_empty() {
+            "Default::default()".to_owned()
+        } else {
+            format!("[{}].into_iter().collect()", entries.join(", "))
+        }
+    }
+
+    /// Reads a string or char literal, including its escapes.
+    fn quoted(&mut self, quote: char) -> String {
+        let mut literal = String::new();
+        literal.extend(self.bump());
+        while let Some(c) = self.bump() {
+            literal.push(c);
+            if c == '\\' {
+                literal.extend(self.bump());
+            } else if c == quote {
+                break;
+            }
+        }
+        literal
+    }
+
+    /// Reads an identifier, path or number.
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            let sign = (c == '-' || c == '+')
+                && (word.is_empty()
+                    || (word.ends_with(['e', 'E'])
+                        && word
+                            .trim_start_matches(['-', '+'])
+                            .starts_with(|c: char| c.is_ascii_digit())));
+            let path = c == ':' && self.chars.get(self.pos + 1) == Some(&':');
+            if path {
+                word.push_str("::");
+                self.pos += 2;
+            } else if c.is_alphanumeric() || c == '_' || c == '.' || sign {
+                word.push(c);
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        word
+    }
+}
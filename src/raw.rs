@@ -0,0 +1,91 @@
+use crate::hex;
+
+/// Prints a hexdump of the bytes a value occupies in memory and returns the
+/// value. This version of the macro will print nothing and be optimized out
+/// in release builds.
+///
+/// This is meant for debugging FFI layout mismatches without transmuting by
+/// hand. The dump shows `size_of_val` bytes, annotated with the target's
+/// endianness:
+///
+/// ```rust
+/// use dbgonly::dbg_raw_bytes;
+/// #[derive(Clone, Copy, Debug)]
+/// #[repr(C)]
+/// struct Header { magic: u32, len: u16, flags: u16 }
+///
+/// let header = Header { magic: 0xfeedface, len: 42, flags: 1 };
+/// // SAFETY: `Header` has no padding.
+/// let header = unsafe { dbg_raw_bytes!(header) };
+/// //                    ^-- prints: [src/main.rs:8] header = 8 bytes, little-endian
+/// //                                0000: ce fa ed fe 2a 00 01 00                          |....*...|
+/// # assert_eq!(header.len, 42);
+/// ```
+///
+/// # Safety
+///
+/// The value must not contain uninitialized bytes, such as the padding
+/// between the fields of a struct, since reading them is undefined behavior.
+/// This is why the macro has to be invoked in an `unsafe` block and is only
+/// available with the `raw_bytes` feature. The value's type must be `Copy`.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_raw_bytes {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                let bytes = $crate::__private::raw_bytes(&tmp);
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::hexdump(bytes)
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_raw_bytes {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__private::raw_bytes_unchecked(&tmp);
+                tmp
+            }
+        }
+    };
+}
+
+/// Returns the bytes of a value.
+///
+/// # Safety
+///
+/// The value must not contain uninitialized bytes.
+pub unsafe fn raw_bytes<T: Copy>(value: &T) -> &[u8] {
+    // SAFETY: `value` is valid for reads of `size_of_val(value)` bytes, and
+    // the caller guarantees that they are all initialized.
+    unsafe { std::slice::from_raw_parts((value as *const T).cast(), std::mem::size_of_val(value)) }
+}
+
+/// Does nothing, but keeps the release version of
+/// [`dbg_raw_bytes!`](crate::dbg_raw_bytes) requiring an `unsafe` block.
+///
+/// # Safety
+///
+/// Always safe to call.
+pub unsafe fn raw_bytes_unchecked<T: Copy>(_: &T) {}
+
+pub fn hexdump(bytes: &[u8]) -> String {
+    let endian = if cfg!(target_endian = "little") {
+        "little-endian"
+    } else {
+        "big-endian"
+    };
+    format!("{} bytes, {}{}", bytes.len(), endian, hex::dump(bytes))
+}
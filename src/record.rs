@@ -0,0 +1,393 @@
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::config::{self, MaxOutput, Timestamps};
+use crate::{group, redact, truncate};
+
+/// A single piece of output produced by one of the `dbgonly` macros.
+///
+/// Records are handed to the active [`Sink`](crate::Sink), which decides how
+/// and where to write them. The [`Display`](fmt::Display) implementation
+/// renders the familiar `[src/main.rs:2] a * 2 = 4` form, taking the
+/// runtime settings such as [`set_deterministic`](crate::set_deterministic)
+/// into account.
+#[derive(Clone, Copy, Debug)]
+pub struct Record<'a> {
+    file: &'static str,
+    line: u32,
+    module_path: &'static str,
+    expr: Option<&'a str>,
+    value: Option<fmt::Arguments<'a>>,
+    fields: &'a [(&'static str, &'a dyn fmt::Debug)],
+    context: Option<&'a str>,
+    depth: usize,
+    thread: Option<&'static str>,
+    time: SystemTime,
+    timestamp: Option<Time>,
+    non_finite: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Time {
+    /// Since `config::start`.
+    Monotonic(Duration),
+    /// Since the Unix epoch.
+    WallClock(Duration),
+}
+
+impl<'a> Record<'a> {
+    pub(crate) fn new(
+        file: &'static str,
+        line: u32,
+        module_path: &'static str,
+        expr: Option<&'a str>,
+        value: Option<fmt::Arguments<'a>>,
+        fields: &'a [(&'static str, &'a dyn fmt::Debug)],
+        context: Option<&'a str>,
+    ) -> Self {
+        let time = now();
+        Record {
+            file,
+            line,
+            module_path,
+            expr,
+            value,
+            fields,
+            context,
+            depth: group::depth(),
+            thread: config::show_thread().then(thread_name),
+            time,
+            timestamp: match config::timestamps() {
+                Timestamps::None => None,
+                Timestamps::Monotonic => Some(Time::Monotonic(config::start().elapsed())),
+                Timestamps::WallClock => time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .ok()
+                    .map(Time::WallClock),
+            },
+            non_finite: false,
+        }
+    }
+
+    /// Marks the value as containing a NaN or infinity, which is flagged when
+    /// it is rendered.
+    pub(crate) fn with_non_finite(mut self, non_finite: bool) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// The source file of the macro invocation.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// The line of the macro invocation.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The module path of the macro invocation, which is empty for records
+    /// reported through functions such as
+    /// [`report_failing_input`](crate::report_failing_input).
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+
+    /// The source code of the expression, or `None` for `dbgonly!()`.
+    pub fn expr(&self) -> Option<&'a str> {
+        self.expr
+    }
+
+    /// The formatted value of the expression, or `None` for `dbgonly!()`.
+    ///
+    /// This is the value exactly as produced by its formatting
+    /// implementation; see [`render_value`](Self::render_value) for the
+    /// post-processed text that is actually printed.
+    pub fn value(&self) -> Option<fmt::Arguments<'a>> {
+        self.value
+    }
+
+    /// Renders the value, applying the runtime post-processing settings such
+    /// as [`set_redact_addresses`](crate::set_redact_addresses) and
+    /// [`set_max_output`](crate::set_max_output).
+    pub fn render_value(&self) -> Option<String> {
+        let max = config::max_output();
+        let value = if max == MaxOutput::default() {
+            self.value?.to_string()
+        } else {
+            truncate::render(self.value?, max)
+        };
+        if config::redact_addresses() {
+            Some(redact::redact_addresses(&value))
+        } else {
+            Some(value)
+        }
+    }
+
+    /// The key-value fields attached at the call site with
+    /// `dbgonly!(expr; key = value, ...)`.
+    pub fn fields(&self) -> &'a [(&'static str, &'a dyn fmt::Debug)] {
+        self.fields
+    }
+
+    /// The context of the emitting thread, as set with
+    /// [`set_context`](crate::set_context) and [`context`](crate::context()).
+    pub fn context(&self) -> Option<&'a str> {
+        self.context
+    }
+
+    /// The number of [`dbg_group!`](crate::dbg_group)s the emitting thread
+    /// was nested in, by which the rendered record is indented.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The name of the emitting thread, if enabled with
+    /// [`set_show_thread`](crate::set_show_thread).
+    pub fn thread(&self) -> Option<&'static str> {
+        self.thread
+    }
+
+    /// The time at which the record was emitted.
+    ///
+    /// It is taken once, when the macro runs, so sinks that buffer records or
+    /// write them on another thread still report when they happened. On
+    /// `wasm32-unknown-unknown`, which has no system clock, it is always the
+    /// Unix epoch.
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn now() -> SystemTime {
+    SystemTime::now()
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn now() -> SystemTime {
+    SystemTime::UNIX_EPOCH
+}
+
+/// Returns the name of the current thread, or its id if it has none.
+pub(crate) fn thread_name() -> &'static str {
+    thread_local! {
+        // Leaked once per thread that needs its name, so that records stay
+        // `Copy`.
+        static NAME: &'static str = {
+            let thread = std::thread::current();
+            let name = match thread.name() {
+                Some(name) => name.to_owned(),
+                None => format!("{:?}", thread.id()),
+            };
+            Box::leak(name.into_boxed_str())
+        };
+    }
+    NAME.with(|name| *name)
+}
+
+/// The escape sequences that the parts of a rendered record are wrapped in.
+struct Style {
+    location: &'static str,
+    expr: &'static str,
+    value: &'static str,
+    err: &'static str,
+    none: &'static str,
+    reset: &'static str,
+}
+
+const PLAIN: Style = Style {
+    location: "",
+    expr: "",
+    value: "",
+    err: "",
+    none: "",
+    reset: "",
+};
+
+#[cfg(feature = "color")]
+const COLOR: Style = Style {
+    location: "\x1b[2m",
+    expr: "\x1b[1m",
+    value: "\x1b[36m",
+    err: "\x1b[1;31m",
+    none: "\x1b[33m",
+    reset: "\x1b[0m",
+};
+
+impl Record<'_> {
+    /// Renders the record like its `Display` implementation, with the
+    /// location, expression and value in distinct colors and `Err` and `None`
+    /// highlighted.
+    #[cfg(feature = "color")]
+    pub(crate) fn colored(&self) -> impl fmt::Display + '_ {
+        struct Colored<'a, 'b>(&'a Record<'b>);
+
+        impl fmt::Display for Colored<'_, '_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.render_indented(f, &COLOR)
+            }
+        }
+
+        Colored(self)
+    }
+
+    fn render_indented(&self, f: &mut fmt::Formatter<'_>, style: &Style) -> fmt::Result {
+        if self.depth == 0 {
+            return self.render(f, style);
+        }
+        self.render(
+            &mut Indented {
+                inner: f,
+                indent: self.depth,
+                line_start: true,
+            },
+            style,
+        )
+    }
+
+    fn render(&self, f: &mut dyn fmt::Write, style: &Style) -> fmt::Result {
+        let deterministic = config::deterministic();
+        f.write_str(style.location)?;
+        f.write_str("[")?;
+        if config::show_run_id() {
+            if deterministic {
+                f.write_str("RUN ")?;
+            } else {
+                write!(f, "{} ", crate::run_id())?;
+            }
+        }
+        match self.timestamp {
+            None => {}
+            Some(_) if deterministic => f.write_str("T+0 ")?,
+            Some(Time::Monotonic(elapsed)) => {
+                write!(f, "+{}.{:03}s ", elapsed.as_secs(), elapsed.subsec_millis())?
+            }
+            Some(Time::WallClock(since_epoch)) => {
+                let secs = since_epoch.as_secs() % 86_400;
+                write!(
+                    f,
+                    "{:02}:{:02}:{:02}.{:03} ",
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60,
+                    since_epoch.subsec_millis()
+                )?;
+            }
+        }
+        if let Some(thread) = self.thread {
+            write!(f, "{} ", thread)?;
+        }
+        if deterministic {
+            let file = self.file.rsplit(['/', '\\']).next().unwrap_or(self.file);
+            write!(f, "{}:LINE]", file)?;
+        } else {
+            write!(f, "{}:{}]", self.file, self.line)?;
+        }
+        f.write_str(style.reset)?;
+        let value = self.render_value();
+        if let Some(expr) = self.expr {
+            write!(f, " {}{}{}", style.expr, expr, style.reset)?;
+            if value.is_some() {
+                f.write_str(" =")?;
+            }
+        }
+        if let Some(value) = &value {
+            f.write_str(" ")?;
+            // Values that bring their own colors, like diffs, are left alone.
+            if style.value.is_empty() || value.contains('\x1b') {
+                f.write_str(value)?;
+            } else {
+                write_highlighted(f, value, style)?;
+            }
+        }
+        if self.non_finite && value.is_some() {
+            f.write_str(" (non-finite)")?;
+        }
+        for (key, value) in self.fields {
+            write!(f, " {}={:?}", key, value)?;
+        }
+        if let Some(context) = self.context {
+            write!(f, " [{}]", context)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a value in the value style, with `Err` and `None` outside of
+/// string and char literals in their own styles.
+fn write_highlighted(f: &mut dyn fmt::Write, value: &str, style: &Style) -> fmt::Result {
+    f.write_str(style.value)?;
+    let bytes = value.as_bytes();
+    let mut written = 0;
+    let mut quote = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match quote {
+            Some(_) if b == b'\\' => i += 1,
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if b == b'"' => quote = Some(b'"'),
+            // Lifetimes never appear in values, so a `'` starts a char.
+            None if b == b'\'' => quote = Some(b'\''),
+            None => {
+                let rest = &value[i..];
+                let boundary = |len: usize| {
+                    (i == 0 || !is_ident(bytes[i - 1]))
+                        && !bytes.get(i + len).copied().is_some_and(is_ident)
+                };
+                let highlight = if rest.starts_with("Err") && boundary(3) {
+                    Some(("Err", style.err))
+                } else if rest.starts_with("None") && boundary(4) {
+                    Some(("None", style.none))
+                } else {
+                    None
+                };
+                if let Some((word, color)) = highlight {
+                    f.write_str(&value[written..i])?;
+                    write!(f, "{}{}{}{}", style.reset, color, word, style.reset)?;
+                    f.write_str(style.value)?;
+                    i += word.len();
+                    written = i;
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    f.write_str(&value[written..])?;
+    f.write_str(style.reset)
+}
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+impl fmt::Display for Record<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render_indented(f, &PLAIN)
+    }
+}
+
+/// Indents every line written through it by four spaces per level.
+struct Indented<'a, 'b> {
+    inner: &'a mut fmt::Formatter<'b>,
+    indent: usize,
+    line_start: bool,
+}
+
+impl fmt::Write for Indented<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.line_start {
+                for _ in 0..self.indent {
+                    self.inner.write_str("    ")?;
+                }
+            }
+            self.inner.write_str(line)?;
+            self.line_start = line.ends_with('\n');
+        }
+        Ok(())
+    }
+}
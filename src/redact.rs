@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// The minimum number of hex digits for a `0x` literal to count as an address,
+/// so that small hex-formatted integers are left alone.
+const MIN_ADDRESS_DIGITS: usize = 8;
+
+static ADDRESSES: Mutex<Option<HashMap<String, usize>>> = Mutex::new(None);
+
+/// Replaces every address-like `0x...` literal in `text` with a placeholder
+/// such as `<addr#1>`. The same address maps to the same placeholder for the
+/// lifetime of the process.
+pub(crate) fn redact_addresses(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("0x") {
+        let (before, candidate) = rest.split_at(start);
+        out.push_str(before);
+        let digits = candidate[2..]
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(candidate.len() - 2);
+        let at_boundary = !out.ends_with(|c: char| c.is_alphanumeric() || c == '_');
+        if at_boundary && digits >= MIN_ADDRESS_DIGITS {
+            let address = &candidate[..2 + digits];
+            out.push_str(&format!("<addr#{}>", placeholder(address)));
+            rest = &candidate[2 + digits..];
+        } else {
+            out.push_str("0x");
+            rest = &candidate[2..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn placeholder(address: &str) -> usize {
+    let mut addresses = ADDRESSES.lock().unwrap_or_else(PoisonError::into_inner);
+    let addresses = addresses.get_or_insert_with(HashMap::new);
+    let next = addresses.len() + 1;
+    *addresses
+        .entry(address.to_ascii_lowercase())
+        .or_insert(next)
+}
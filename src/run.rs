@@ -0,0 +1,33 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+static RUN_ID: OnceLock<String> = OnceLock::new();
+
+/// Returns a short random identifier for the current run of the program.
+///
+/// The identifier is generated on first use and stays the same for the rest
+/// of the process. It is included in every printed record when enabled with
+/// [`set_show_run_id`](crate::set_show_run_id), which makes it possible to
+/// tell apart lines pasted from several runs.
+///
+/// ```rust
+/// let id = dbgonly::run_id();
+/// assert_eq!(id.len(), 8);
+/// assert_eq!(id, dbgonly::run_id());
+/// ```
+pub fn run_id() -> &'static str {
+    RUN_ID.get_or_init(|| {
+        let mut hasher = RandomState::new().build_hasher();
+        // Miri rejects reading the wall clock under isolation; `RandomState`
+        // alone is random enough there.
+        if !cfg!(miri) {
+            hasher.write_u32(std::process::id());
+            if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+                hasher.write_u128(elapsed.as_nanos());
+            }
+        }
+        format!("{:08x}", hasher.finish() as u32)
+    })
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::thread;
+use std::time::Instant;
+
+use crate::{config, group, Callsite};
+
+/// Prints a line when the enclosing block is entered and another one with
+/// the elapsed time when it is left. This version of the macro will print
+/// nothing and expand to nothing in release builds.
+///
+/// Unlike [`dbg_group!`](crate::dbg_group), the macro does not wrap a block
+/// but guards the rest of the one it is written in, which makes it easy to
+/// add to the top of a function:
+///
+/// ```rust
+/// use dbgonly::dbg_scope;
+/// fn parse_header(bytes: &[u8]) -> Option<u16> {
+///     dbg_scope!("parsing header");
+///     //  ^-- prints: [src/main.rs:3] enter parsing header
+///     let len = bytes.get(..2)?;
+///     Some(u16::from_be_bytes([len[0], len[1]]))
+///     //  ^-- prints: [src/main.rs:3] exit parsing header took 3.1µs
+/// }
+/// # assert_eq!(parse_header(&[0, 7]), Some(7));
+/// # assert_eq!(parse_header(&[]), None);
+/// ```
+///
+/// The exit is printed however the block is left, including an early
+/// `return`, `?` or a panic, in which case it reads `exit parsing header
+/// (panicked) took 3.1µs`. In [deterministic](crate::set_deterministic)
+/// mode, the elapsed time is printed as `ELAPSED`. To indent the records
+/// printed inside a scope, see
+/// [`set_indent_scopes`](crate::set_indent_scopes).
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_scope {
+    ($label:expr $(,)?) => {
+        let _scope = $crate::__private::Scope::enter(
+            {
+                static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+                    file!(),
+                    line!(),
+                    module_path!(),
+                    ::core::option::Option::None,
+                )
+                .silenced_if($crate::__silenced!());
+                &CALLSITE
+            },
+            $label,
+        );
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_scope {
+    ($label:expr $(,)?) => {
+        if false {
+            let _ = &$label;
+        }
+    };
+}
+
+/// An entered [`dbg_scope!`], which prints the exit when dropped.
+pub struct Scope {
+    callsite: &'static Callsite,
+    /// The label, if the entry was printed.
+    label: Option<String>,
+    start: Instant,
+    /// The depth to restore on exit, if the records in between are indented.
+    depth: Option<usize>,
+}
+
+impl Scope {
+    pub fn enter(callsite: &'static Callsite, label: impl fmt::Display) -> Self {
+        let label = callsite.is_enabled().then(|| {
+            let label = label.to_string();
+            crate::__private::emit(callsite, Some(format_args!("enter {}", label)), &[]);
+            label
+        });
+        let depth = (label.is_some() && config::indent_scopes()).then(group::indent);
+        Scope {
+            callsite,
+            label,
+            start: Instant::now(),
+            depth,
+        }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        if let Some(depth) = self.depth {
+            group::restore_depth(depth);
+        }
+        if let Some(label) = &self.label {
+            let panicked = if thread::panicking() {
+                " (panicked)"
+            } else {
+                ""
+            };
+            if config::deterministic() {
+                crate::__private::emit(
+                    self.callsite,
+                    Some(format_args!("exit {}{} took ELAPSED", label, panicked)),
+                    &[],
+                );
+            } else {
+                crate::__private::emit(
+                    self.callsite,
+                    Some(format_args!(
+                        "exit {}{} took {:?}",
+                        label, panicked, elapsed
+                    )),
+                    &[],
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Recording values in debug runs and replaying them in tests.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const DEFAULT_SESSION_FILE: &str = "dbgonly-session.jsonl";
+
+static SESSION: Mutex<Option<PathBuf>> = Mutex::new(None);
+static WRITER: Mutex<Option<File>> = Mutex::new(None);
+
+/// Serializes the value of an expression under a name into the session file
+/// and returns the value. This version of the macro will record nothing and
+/// be optimized out in release builds.
+///
+/// Recorded values can be loaded back with [`replay`], which turns an
+/// interesting input seen while debugging into a regression fixture:
+///
+/// ```rust,no_run
+/// use dbgonly::dbg_record;
+/// # fn parse(input: &str) -> Vec<u32> { vec![] }
+/// # let input = "";
+/// let tokens = dbg_record!("weird_tokens", parse(input));
+///
+/// // Later, in a test:
+/// let tokens: Vec<u32> = dbgonly::replay("weird_tokens").unwrap();
+/// ```
+///
+/// Values are appended to the file set with [`set_session_file`], which
+/// defaults to `dbgonly-session.jsonl` in the current directory. If the same
+/// name is recorded several times, the last value wins.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_record {
+    ($name:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__private::record_value($name, &tmp);
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_record {
+    ($name:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+}
+
+/// Sets the file that [`dbg_record!`](crate::dbg_record) appends to and
+/// [`replay`] reads from.
+pub fn set_session_file(path: impl Into<PathBuf>) {
+    *SESSION.lock().unwrap_or_else(PoisonError::into_inner) = Some(path.into());
+    *WRITER.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+fn session_file() -> PathBuf {
+    SESSION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SESSION_FILE))
+}
+
+/// Loads the value most recently recorded under `name` with
+/// [`dbg_record!`](crate::dbg_record).
+///
+/// Fails with [`io::ErrorKind::NotFound`] if nothing was recorded under that
+/// name and with [`io::ErrorKind::InvalidData`] if the value cannot be
+/// deserialized as a `T`.
+pub fn replay<T: DeserializeOwned>(name: &str) -> io::Result<T> {
+    replay_from(&session_file(), name)
+}
+
+fn replay_from<T: DeserializeOwned>(path: &Path, name: &str) -> io::Result<T> {
+    let mut found = None;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let entry: serde_json::Value = serde_json::from_str(&line?)?;
+        if entry["name"] == name {
+            found = Some(entry["value"].clone());
+        }
+    }
+    let value = found.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no value recorded under {:?} in {}", name, path.display()),
+        )
+    })?;
+    Ok(serde_json::from_value(value)?)
+}
+
+pub fn record_value<T: Serialize + ?Sized>(name: &str, value: &T) {
+    let line = match serde_json::to_string(&serde_json::json!({ "name": name, "value": value })) {
+        Ok(line) => line,
+        Err(err) => {
+            eprintln!("[dbgonly] cannot record {:?}: {}", name, err);
+            return;
+        }
+    };
+    let mut writer = WRITER.lock().unwrap_or_else(PoisonError::into_inner);
+    if writer.is_none() {
+        let path = session_file();
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => *writer = Some(file),
+            Err(err) => {
+                eprintln!("[dbgonly] cannot open {}: {}", path.display(), err);
+                return;
+            }
+        }
+    }
+    if let Some(file) = writer.as_mut() {
+        let _ = writeln!(file, "{}", line);
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use super::Sink;
+use crate::{diff, Record};
+
+/// The values printed at each call site, keyed by file and line.
+type Values = BTreeMap<(String, u32), Vec<String>>;
+
+/// A sink that compares the printed values of a run against a baseline run.
+///
+/// In record mode, the rendered value of every record is collected per call
+/// site and written to the baseline file whenever the sink is flushed, which
+/// happens at the latest when the program exits. In compare mode, the values
+/// are instead compared against the baseline file, and the call sites whose
+/// values differ are reported with a diff on stderr. The report is printed
+/// when the sink is flushed, and only again if it changed since:
+///
+/// ```text,ignore
+/// [dbgonly] 1 of 3 call sites differ from good-run.dbg:
+/// [src/main.rs:12] value 2 of 4:
+///       Config {
+///     -     retries: 3,
+///     +     retries: 0,
+///       }
+/// ```
+///
+/// [`Baseline::open`] records if the file does not exist yet and compares
+/// otherwise, so the same program can be run once on the good input and then
+/// on the bad one. Only the values are compared, so it can be combined with
+/// [`Tee`](super::Tee) to keep the regular output:
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, Baseline, Stderr, Tee};
+/// sink::set_sink(Tee(Stderr, Baseline::open("good-run.dbg").unwrap()));
+/// ```
+///
+/// Consider enabling [`set_redact_addresses`](crate::set_redact_addresses)
+/// so that pointers do not differ between runs.
+#[derive(Debug)]
+pub struct Baseline {
+    path: PathBuf,
+    baseline: Option<Values>,
+    values: Mutex<Values>,
+    /// The last report printed in compare mode.
+    reported: Mutex<Option<String>>,
+}
+
+impl Baseline {
+    /// Compares against the baseline file at `path` if it exists, and records
+    /// a new one otherwise.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Baseline {
+                path: path.to_owned(),
+                baseline: Some(parse(&text)?),
+                values: Mutex::new(Values::new()),
+                reported: Mutex::new(None),
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Baseline::record(path)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Records a new baseline file at `path`, replacing any existing one.
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Baseline {
+            path: path.as_ref().to_owned(),
+            baseline: None,
+            values: Mutex::new(Values::new()),
+            reported: Mutex::new(None),
+        }
+    }
+}
+
+impl Sink for Baseline {
+    fn emit(&self, record: &Record<'_>) {
+        let Some(value) = record.render_value() else {
+            return;
+        };
+        self.values
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry((record.file().to_owned(), record.line()))
+            .or_default()
+            .push(value);
+    }
+
+    fn flush(&self) {
+        let values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+        match &self.baseline {
+            None => {
+                let _ = fs::write(&self.path, serialize(&values));
+            }
+            Some(baseline) => {
+                // The sink is flushed several times on the way out, but the
+                // report is only printed again if something changed.
+                let report = compare(baseline, &values, &self.path);
+                let mut reported = self.reported.lock().unwrap_or_else(PoisonError::into_inner);
+                if reported.as_ref() != Some(&report) {
+                    let mut stderr = io::stderr().lock();
+                    let _ = stderr.write_all(report.as_bytes());
+                    *reported = Some(report);
+                }
+            }
+        }
+    }
+}
+
+/// Serializes values as a header line with the tab-separated file, line and
+/// length in bytes of each value, followed by the value itself and a line
+/// break, so that values keep their own line breaks exactly.
+fn serialize(values: &Values) -> String {
+    let mut out = String::new();
+    for ((file, line), values) in values {
+        for value in values {
+            let _ = writeln!(out, "{}\t{}\t{}", file, line, value.len());
+            out.push_str(value);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn parse(mut text: &str) -> io::Result<Values> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed baseline file");
+    let mut values = Values::new();
+    while let Some((header, rest)) = text.split_once('\n') {
+        let mut parts = header.rsplitn(3, '\t');
+        let len: usize = parts
+            .next()
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(invalid)?;
+        let line: u32 = parts
+            .next()
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(invalid)?;
+        let file = parts.next().ok_or_else(invalid)?;
+        let value = rest.get(..len).ok_or_else(invalid)?;
+        text = rest[len..].strip_prefix('\n').ok_or_else(invalid)?;
+        values
+            .entry((file.to_owned(), line))
+            .or_default()
+            .push(value.to_owned());
+    }
+    if !text.is_empty() {
+        return Err(invalid());
+    }
+    Ok(values)
+}
+
+/// The number of differing values shown per call site.
+const MAX_SHOWN: usize = 3;
+
+fn compare(baseline: &Values, values: &Values, path: &Path) -> String {
+    let mut keys: Vec<&(String, u32)> = baseline.keys().chain(values.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let mut out = String::new();
+    let mut differing = 0;
+    let empty = Vec::new();
+    for key in &keys {
+        let old = baseline.get(key).unwrap_or(&empty);
+        let new = values.get(key).unwrap_or(&empty);
+        if old == new {
+            continue;
+        }
+        differing += 1;
+        let (file, line) = key;
+        if new.is_empty() {
+            let _ = writeln!(
+                out,
+                "[{}:{}] not reached, but was in the baseline",
+                file, line
+            );
+            continue;
+        }
+        if old.is_empty() {
+            let _ = writeln!(
+                out,
+                "[{}:{}] reached, but was not in the baseline",
+                file, line
+            );
+            continue;
+        }
+        if old.len() != new.len() {
+            let _ = writeln!(
+                out,
+                "[{}:{}] printed {} values, the baseline {}",
+                file,
+                line,
+                new.len(),
+                old.len()
+            );
+        }
+        let changed: Vec<usize> = (0..old.len().min(new.len()))
+            .filter(|&i| old[i] != new[i])
+            .collect();
+        for &i in changed.iter().take(MAX_SHOWN) {
+            let _ = writeln!(out, "[{}:{}] value {} of {}:", file, line, i + 1, new.len());
+            out.push_str(&diff::render(&old[i], &new[i], "    "));
+        }
+        if changed.len() > MAX_SHOWN {
+            let _ = writeln!(
+                out,
+                "[{}:{}] ... and {} more differing values",
+                file,
+                line,
+                changed.len() - MAX_SHOWN
+            );
+        }
+    }
+    let header = if differing == 0 {
+        format!(
+            "[dbgonly] all {} call sites match {}\n",
+            keys.len(),
+            path.display()
+        )
+    } else {
+        format!(
+            "[dbgonly] {} of {} call sites differ from {}:\n",
+            differing,
+            keys.len(),
+            path.display()
+        )
+    };
+    header + &out
+}
@@ -0,0 +1,24 @@
+use wasm_bindgen::JsValue;
+use web_sys::console;
+
+use super::Sink;
+use crate::Record;
+
+/// A sink that writes records to the browser console with `console.debug`.
+///
+/// On `wasm32-unknown-unknown`, stderr goes nowhere, so this is the default
+/// sink there when the `wasm` feature is enabled. Records show up in the
+/// developer tools at the "Verbose" level, formatted exactly like on stderr:
+///
+/// ```rust,ignore
+/// use dbgonly::sink::{self, Console};
+/// sink::set_sink(Console);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Console;
+
+impl Sink for Console {
+    fn emit(&self, record: &Record<'_>) {
+        console::debug_1(&JsValue::from_str(&record.to_string()));
+    }
+}
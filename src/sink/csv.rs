@@ -0,0 +1,82 @@
+use std::collections::hash_map::{Entry, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
+
+use super::Sink;
+use crate::Record;
+
+/// A sink that appends numeric values to one CSV file per call site.
+///
+/// Every record whose value is a plain number (integers and floats,
+/// including `NaN` and `inf`) is appended as a `timestamp,value` row to a file
+/// named after its call site, such as `src_main.rs_12.csv`, in the given
+/// directory. The timestamp is the record's [time](Record::time) in seconds
+/// since the Unix epoch. Other records are ignored, so this sink is usually
+/// combined with another one using [`Tee`](super::Tee):
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, Csv, Stderr, Tee};
+/// sink::set_sink(Tee(Stderr, Csv::new("target/dbgonly-csv").unwrap()));
+/// for step in 0..100 {
+///     let _ = dbgonly::dbgonly!(f64::from(step).sqrt());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Csv {
+    dir: PathBuf,
+    files: Mutex<HashMap<(&'static str, u32), File>>,
+}
+
+impl Csv {
+    /// Creates a sink that writes into `dir`, creating it if necessary.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Csv {
+            dir,
+            files: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn open(&self, record: &Record<'_>) -> io::Result<File> {
+        // Paths from other crates can be absolute, also with a drive letter
+        // on Windows, so everything that would leave `dir` is replaced.
+        let name =
+            format!("{}_{}.csv", record.file(), record.line()).replace(['/', '\\', ':'], "_");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(name))?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "timestamp,value")?;
+        }
+        Ok(file)
+    }
+}
+
+impl Sink for Csv {
+    fn emit(&self, record: &Record<'_>) {
+        let Some(value) = record.render_value() else {
+            return;
+        };
+        let Ok(value) = value.trim().parse::<f64>() else {
+            return;
+        };
+        let ts = record
+            .time()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        let mut files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+        let file = match files.entry((record.file(), record.line())) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match self.open(record) {
+                Ok(file) => entry.insert(file),
+                Err(_) => return,
+            },
+        };
+        let _ = writeln!(file, "{:.6},{}", ts, value);
+    }
+}
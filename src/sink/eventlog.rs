@@ -0,0 +1,94 @@
+use std::ffi::c_void;
+use std::io;
+use std::ptr;
+
+use super::Sink;
+use crate::Record;
+
+type Handle = *mut c_void;
+
+const EVENTLOG_INFORMATION_TYPE: u16 = 0x0004;
+
+#[link(name = "advapi32")]
+extern "system" {
+    fn RegisterEventSourceW(server_name: *const u16, source_name: *const u16) -> Handle;
+    fn DeregisterEventSource(event_log: Handle) -> i32;
+    fn ReportEventW(
+        event_log: Handle,
+        event_type: u16,
+        category: u16,
+        event_id: u32,
+        user_sid: *mut c_void,
+        num_strings: u16,
+        data_size: u32,
+        strings: *const *const u16,
+        raw_data: *mut c_void,
+    ) -> i32;
+}
+
+/// A sink that writes to the Windows Event Log of the local machine.
+///
+/// Every record is reported as an informational event under the given
+/// source name, so it shows up in Event Viewer (`Windows Logs > Application`)
+/// even for services that have no console. Records that cannot be reported
+/// are dropped.
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, EventLog};
+/// sink::set_sink(EventLog::new("my-service").expect("cannot open event log"));
+/// ```
+#[derive(Debug)]
+pub struct EventLog {
+    handle: Handle,
+}
+
+// SAFETY: event log handles may be used from any thread.
+unsafe impl Send for EventLog {}
+unsafe impl Sync for EventLog {}
+
+impl EventLog {
+    /// Opens the event log of the local machine under `source`.
+    pub fn new(source: &str) -> io::Result<Self> {
+        let source = to_wide(source);
+        // SAFETY: `source` is a valid NUL-terminated UTF-16 string.
+        let handle = unsafe { RegisterEventSourceW(ptr::null(), source.as_ptr()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(EventLog { handle })
+    }
+}
+
+impl Sink for EventLog {
+    fn emit(&self, record: &Record<'_>) {
+        let message = to_wide(&record.to_string());
+        let strings = [message.as_ptr()];
+        // SAFETY: `strings` holds one valid NUL-terminated UTF-16 string.
+        unsafe {
+            ReportEventW(
+                self.handle,
+                EVENTLOG_INFORMATION_TYPE,
+                0,
+                0,
+                ptr::null_mut(),
+                1,
+                0,
+                strings.as_ptr(),
+                ptr::null_mut(),
+            );
+        }
+    }
+}
+
+impl Drop for EventLog {
+    fn drop(&mut self) {
+        // SAFETY: the handle was returned by `RegisterEventSourceW`.
+        unsafe {
+            DeregisterEventSource(self.handle);
+        }
+    }
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
@@ -0,0 +1,177 @@
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use super::Sink;
+use crate::Record;
+
+/// A sink that appends every record as a line to a file.
+///
+/// The file is opened in append mode and every record, including its line
+/// break, is written as a whole with `write_all`, which takes a single
+/// `write` call on regular files, so several processes can share one file
+/// without their records getting interleaved. Each line starts with a tag
+/// identifying the writing process, which defaults to `pid <id>`:
+///
+/// ```text,ignore
+/// [pid 4711] [src/worker.rs:12] job = 3
+/// [pid 4712] [src/worker.rs:12] job = 4
+/// ```
+///
+/// On file systems where appends are not atomic, such as NFS, an advisory
+/// lock can additionally be held around each write with
+/// [`with_lock`](File::with_lock). Records that cannot be written are
+/// dropped.
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, File};
+/// sink::set_sink(File::open("debug.log").unwrap().with_tag("parent"));
+/// ```
+///
+/// To keep long-running programs from filling the disk, the file can be
+/// rotated once it grows past a given size with
+/// [`with_rotation`](File::with_rotation).
+#[derive(Debug)]
+pub struct File {
+    path: PathBuf,
+    file: Mutex<fs::File>,
+    tag: String,
+    lock: bool,
+    rotation: Option<(u64, usize)>,
+}
+
+impl File {
+    /// Opens the file at `path` for appending, creating it if it does not
+    /// exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let file = open(&path)?;
+        Ok(File {
+            path,
+            file: Mutex::new(file),
+            tag: format!("pid {}", std::process::id()),
+            lock: false,
+            rotation: None,
+        })
+    }
+
+    /// Sets the tag that every line starts with.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = tag.into();
+        self
+    }
+
+    /// Sets whether an exclusive advisory lock on the file is held while
+    /// writing each record.
+    pub fn with_lock(mut self, lock: bool) -> Self {
+        self.lock = lock;
+        self
+    }
+
+    /// Rotates the file once it is larger than `max_bytes`, keeping the
+    /// `keep` most recent old files next to it as `debug.log.1`,
+    /// `debug.log.2` and so on, with `.1` being the newest.
+    ///
+    /// ```rust,no_run
+    /// use dbgonly::sink::{self, File};
+    /// sink::set_sink(File::open("debug.log").unwrap().with_rotation(10 << 20, 3));
+    /// ```
+    ///
+    /// Rotation is not coordinated between processes that share a file.
+    pub fn with_rotation(mut self, max_bytes: u64, keep: usize) -> Self {
+        self.rotation = Some((max_bytes, keep));
+        self
+    }
+
+    /// Moves the file to `.1`, shifting the older ones up, and starts a new
+    /// one.
+    fn rotate(&self, file: &mut fs::File, keep: usize) -> io::Result<()> {
+        let numbered = |n: usize| {
+            let mut path = OsString::from(&self.path);
+            path.push(format!(".{}", n));
+            PathBuf::from(path)
+        };
+        if keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..keep).rev() {
+                let _ = fs::rename(numbered(n), numbered(n + 1));
+            }
+            fs::rename(&self.path, numbered(1))?;
+        }
+        *file = open(&self.path)?;
+        Ok(())
+    }
+}
+
+fn open(path: &Path) -> io::Result<fs::File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+impl Sink for File {
+    fn emit(&self, record: &Record<'_>) {
+        let line = format!("[{}] {}\n", self.tag, record);
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.lock && file.lock().is_err() {
+            return;
+        }
+        let _ = file.write_all(line.as_bytes());
+        if self.lock {
+            let _ = file.unlock();
+        }
+        if let Some((max_bytes, keep)) = self.rotation {
+            if file.metadata().is_ok_and(|m| m.len() > max_bytes) {
+                let _ = self.rotate(&mut file, keep);
+            }
+        }
+    }
+}
+
+/// Routes the output of all `dbgonly` macros to a file, appending to it.
+///
+/// This is a shorthand for installing a [`File`] sink with
+/// [`set_sink`](super::set_sink), which keeps the output from destroying the
+/// screen of terminal user interfaces:
+///
+/// ```rust,no_run
+/// dbgonly::to_file("/tmp/debug.log").unwrap();
+/// ```
+///
+/// Without any code changes, the same can be done by setting the
+/// `DBGONLY_FILE` environment variable to the path, in which case the file is
+/// rotated once it grows past `DBGONLY_FILE_MAX_BYTES`, if that is set,
+/// keeping one old file. A sink installed in code takes precedence.
+pub fn to_file(path: impl AsRef<Path>) -> io::Result<()> {
+    super::set_sink(File::open(path)?);
+    Ok(())
+}
+
+/// Returns the sink configured with `DBGONLY_FILE`, opening it the first
+/// time.
+pub(crate) fn from_env() -> Option<&'static File> {
+    static FILE: OnceLock<Option<File>> = OnceLock::new();
+    FILE.get_or_init(|| {
+        let path = std::env::var_os("DBGONLY_FILE").filter(|path| !path.is_empty())?;
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!(
+                    "[dbgonly] cannot open DBGONLY_FILE {}: {}",
+                    Path::new(&path).display(),
+                    e
+                );
+                return None;
+            }
+        };
+        let max_bytes = std::env::var("DBGONLY_FILE_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok());
+        Some(match max_bytes {
+            Some(max_bytes) => file.with_rotation(max_bytes, 1),
+            None => file,
+        })
+    })
+    .as_ref()
+}
@@ -0,0 +1,49 @@
+use std::panic;
+use std::sync::Once;
+
+/// Makes sure pending tables are printed and the active sink is flushed when
+/// the program panics or exits, so the last records before a crash are not
+/// lost in a buffer, and that requested reports are printed when it exits.
+pub(crate) fn install_guards() {
+    static INSTALL: Once = Once::new();
+    if cfg!(any(miri, dbgonly_sanitizer)) {
+        return;
+    }
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        // Panics may be caught, so the reports are left to the exit, which
+        // also follows a panic that ends the program.
+        panic::set_hook(Box::new(move |info| {
+            flush_all();
+            previous(info);
+        }));
+        register_atexit();
+    });
+}
+
+fn flush_all() {
+    crate::table::flush_all();
+    super::flush();
+}
+
+#[cfg(any(unix, windows))]
+fn register_atexit() {
+    extern "C" {
+        fn atexit(callback: extern "C" fn()) -> std::ffi::c_int;
+    }
+
+    extern "C" fn flush_at_exit() {
+        flush_all();
+        crate::count::report_at_exit();
+        crate::stats::report_at_exit();
+    }
+
+    // SAFETY: `flush_at_exit` is a plain function that stays valid for the
+    // lifetime of the process.
+    unsafe {
+        atexit(flush_at_exit);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn register_atexit() {}
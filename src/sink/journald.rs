@@ -0,0 +1,174 @@
+use std::fs;
+use std::io::{self, Write};
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::net::UnixDatagram;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::Sink;
+use crate::Record;
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// A sink that writes to the systemd journal using its native protocol.
+///
+/// Besides the rendered `MESSAGE`, every entry carries the structured fields
+/// `CODE_FILE`, `CODE_LINE`, `DBG_MODULE`, `DBG_EXPR`, `DBG_VALUE` and
+/// `PRIORITY=7` (debug), plus `DBG_FIELD_<KEY>` for every
+/// [key-value field](crate::dbgonly), `DBG_CONTEXT` for the
+/// [thread's context](crate::set_context) and `DBG_RUN_ID` when the
+/// [run identifier](crate::set_show_run_id) is shown, so output can be
+/// narrowed down with `journalctl`:
+///
+/// ```text,ignore
+/// journalctl CODE_FILE=src/main.rs DBG_EXPR='n * factorial(n - 1)'
+/// ```
+///
+/// Entries too large for a single datagram, such as records of big
+/// collections, are passed to the journal in a sealed memory file instead,
+/// as its protocol allows. Records that cannot be delivered at all are
+/// dropped, and the first such failure is reported on stderr.
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, Journald};
+/// sink::set_sink(Journald::new().expect("journald is not running"));
+/// ```
+#[derive(Debug)]
+pub struct Journald {
+    socket: UnixDatagram,
+    identifier: Option<String>,
+    reported: AtomicBool,
+}
+
+impl Journald {
+    /// Connects to the journal socket of the running systemd instance.
+    pub fn new() -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNAL_SOCKET)?;
+        Ok(Journald {
+            socket,
+            identifier: std::env::args().next().and_then(|arg0| {
+                std::path::Path::new(&arg0)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            }),
+            reported: AtomicBool::new(false),
+        })
+    }
+
+    /// Sets the `SYSLOG_IDENTIFIER` attached to every entry, which defaults
+    /// to the name of the running executable.
+    pub fn with_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.identifier = Some(identifier.into());
+        self
+    }
+
+    /// Sends an entry that does not fit into a datagram by writing it to a
+    /// sealed memory file and passing only its descriptor.
+    fn send_memfd(&self, buf: &[u8]) -> io::Result<()> {
+        // SAFETY: the name is a valid NUL-terminated string.
+        let fd = unsafe {
+            libc::memfd_create(
+                c"dbgonly-journal".as_ptr(),
+                libc::MFD_ALLOW_SEALING | libc::MFD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` was just created and is owned by nobody else.
+        let mut file = fs::File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        file.write_all(buf)?;
+        let seals =
+            libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+        // SAFETY: `fd` stays open as long as `file` is alive.
+        if unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // The entry itself is passed as an `SCM_RIGHTS` control message
+        // without any payload. A `u64` array keeps it suitably aligned.
+        // SAFETY: `CMSG_SPACE` only does arithmetic.
+        const SPACE: usize =
+            unsafe { libc::CMSG_SPACE(mem::size_of::<libc::c_int>() as u32) } as usize;
+        let mut control = [0u64; SPACE.div_ceil(mem::size_of::<u64>())];
+        // SAFETY: all-zero is a valid `msghdr` without any buffers.
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = SPACE as _;
+        // SAFETY: `control` has room for a header and one descriptor, so the
+        // first header is not null and its data is in bounds.
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(mem::size_of::<libc::c_int>() as u32) as _;
+            ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<libc::c_int>(), fd);
+        }
+        // SAFETY: `msg` only points into `control`, which is still alive.
+        if unsafe { libc::sendmsg(self.socket.as_raw_fd(), &msg, libc::MSG_NOSIGNAL) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Sink for Journald {
+    fn emit(&self, record: &Record<'_>) {
+        let mut buf = Vec::new();
+        append_field(&mut buf, "MESSAGE", record.to_string().as_bytes());
+        append_field(&mut buf, "PRIORITY", b"7");
+        append_field(&mut buf, "CODE_FILE", record.file().as_bytes());
+        append_field(&mut buf, "CODE_LINE", record.line().to_string().as_bytes());
+        append_field(&mut buf, "DBG_MODULE", record.module_path().as_bytes());
+        if let Some(expr) = record.expr() {
+            append_field(&mut buf, "DBG_EXPR", expr.as_bytes());
+        }
+        if let Some(value) = record.render_value() {
+            append_field(&mut buf, "DBG_VALUE", value.as_bytes());
+        }
+        for (key, value) in record.fields() {
+            let key = format!("DBG_FIELD_{}", key.to_ascii_uppercase());
+            append_field(&mut buf, &key, format!("{:?}", value).as_bytes());
+        }
+        if let Some(context) = record.context() {
+            append_field(&mut buf, "DBG_CONTEXT", context.as_bytes());
+        }
+        if crate::show_run_id() {
+            append_field(&mut buf, "DBG_RUN_ID", crate::run_id().as_bytes());
+        }
+        if let Some(identifier) = &self.identifier {
+            append_field(&mut buf, "SYSLOG_IDENTIFIER", identifier.as_bytes());
+        }
+        let result = match self.socket.send(&buf) {
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EMSGSIZE | libc::ENOBUFS)) => {
+                self.send_memfd(&buf)
+            }
+            result => result.map(drop),
+        };
+        if let Err(e) = result {
+            if !self.reported.swap(true, Ordering::Relaxed) {
+                eprintln!(
+                    "[dbgonly] dropped a journal entry of {} bytes: {}",
+                    buf.len(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Appends a field in the journal's native format, switching to the
+/// length-prefixed binary form for values that span multiple lines.
+fn append_field(buf: &mut Vec<u8>, key: &str, value: &[u8]) {
+    buf.extend_from_slice(key.as_bytes());
+    if value.contains(&b'\n') {
+        buf.push(b'\n');
+        buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        buf.push(b'=');
+    }
+    buf.extend_from_slice(value);
+    buf.push(b'\n');
+}
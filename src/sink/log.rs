@@ -0,0 +1,54 @@
+use super::Sink;
+use crate::Record;
+
+/// A sink that emits every record as a `Debug` level message through the
+/// [`log`](https://docs.rs/log) facade.
+///
+/// The target is the module path of the call site, so records are filtered
+/// and formatted by the installed logger like the rest of the program's log
+/// messages. The message is the record rendered like on stderr.
+///
+/// With the `log` feature, this is also where records go when no other sink
+/// is installed, unless the `tracing` feature is enabled as well.
+///
+/// ```rust
+/// use dbgonly::sink::{self, Log};
+/// sink::set_sink(Log);
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// # sink::reset_sink();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Log;
+
+impl Sink for Log {
+    fn emit(&self, record: &Record<'_>) {
+        if log::Level::Debug > log::max_level() {
+            return;
+        }
+        let target = match record.module_path() {
+            "" => "dbgonly",
+            module_path => module_path,
+        };
+        let metadata = log::Metadata::builder()
+            .level(log::Level::Debug)
+            .target(target)
+            .build();
+        let logger = log::logger();
+        if !logger.enabled(&metadata) {
+            return;
+        }
+        logger.log(
+            &log::Record::builder()
+                .metadata(metadata)
+                .args(format_args!("{}", record))
+                .module_path_static(Some(target))
+                .file_static(Some(record.file()))
+                .line(Some(record.line()))
+                .build(),
+        );
+    }
+
+    fn flush(&self) {
+        log::logger().flush();
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::ptr;
+use std::sync::atomic::{self, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use memmap2::MmapMut;
+
+use super::Sink;
+use crate::Record;
+
+const MAGIC: &[u8; 8] = b"DBGONLY1";
+const HEADER_LEN: usize = 32;
+const SLOT_HEADER_LEN: usize = 12;
+
+/// A ring buffer sink backed by a memory-mapped file.
+///
+/// Records are written into fixed-size slots of a file that is mapped into
+/// memory, so they end up in the operating system's page cache right away.
+/// If the process is killed with `SIGKILL` or crashes hard, the most recent
+/// records can still be recovered from the file with [`MmapRing::read`] —
+/// a flight recorder for debugging. Records longer than a slot are
+/// truncated.
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, MmapRing};
+/// sink::set_sink(MmapRing::create("flight.dbg", 4096, 512).unwrap());
+///
+/// // After the crash, possibly from another process:
+/// for record in MmapRing::read("flight.dbg").unwrap() {
+///     println!("{}", record);
+/// }
+/// ```
+///
+/// The file consists of a 32-byte header (the magic `DBGONLY1`, followed by
+/// the slot count, the slot size and the number of records written so far as
+/// little-endian `u64`s) and the slots. Each slot starts with the record's
+/// sequence number as a little-endian `u64` (zero for an empty slot) and its
+/// length as a little-endian `u32`, followed by the UTF-8 text.
+#[derive(Debug)]
+pub struct MmapRing {
+    map: Mutex<MmapMut>,
+    slots: usize,
+    slot_size: usize,
+}
+
+impl MmapRing {
+    /// Creates or truncates the file at `path` and maps it as a ring buffer of
+    /// `slots` slots of `slot_size` bytes each.
+    pub fn create(path: impl AsRef<Path>, slots: usize, slot_size: usize) -> io::Result<Self> {
+        if slots == 0 || slot_size <= SLOT_HEADER_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the ring buffer needs at least one slot with room for data",
+            ));
+        }
+        let len = file_len(slots, slot_size).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the ring buffer is too large")
+        })?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(len as u64)?;
+        // SAFETY: the file was just created and sized by us; concurrent
+        // modification by other processes is the caller's responsibility.
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        map[..8].copy_from_slice(MAGIC);
+        map[8..16].copy_from_slice(&(slots as u64).to_le_bytes());
+        map[16..24].copy_from_slice(&(slot_size as u64).to_le_bytes());
+        Ok(MmapRing {
+            map: Mutex::new(map),
+            slots,
+            slot_size,
+        })
+    }
+
+    /// Reads the records stored in a ring buffer file, oldest first.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<String>> {
+        let data = fs::read(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a dbgonly ring buffer");
+        if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+            return Err(invalid());
+        }
+        let slots = usize::try_from(read_u64(&data[8..16])).map_err(|_| invalid())?;
+        let slot_size = usize::try_from(read_u64(&data[16..24])).map_err(|_| invalid())?;
+        let len = file_len(slots, slot_size).ok_or_else(invalid)?;
+        if slot_size <= SLOT_HEADER_LEN || data.len() < len {
+            return Err(invalid());
+        }
+        let mut records: Vec<(u64, String)> = data[HEADER_LEN..len]
+            .chunks_exact(slot_size)
+            .filter_map(|slot| {
+                let seq = read_u64(&slot[..8]);
+                let len = u32::from_le_bytes(slot[8..12].try_into().unwrap()) as usize;
+                let text = slot[SLOT_HEADER_LEN..].get(..len)?;
+                (seq != 0).then(|| (seq, String::from_utf8_lossy(text).into_owned()))
+            })
+            .collect();
+        records.sort_by_key(|&(seq, _)| seq);
+        Ok(records.into_iter().map(|(_, text)| text).collect())
+    }
+}
+
+impl Sink for MmapRing {
+    fn emit(&self, record: &Record<'_>) {
+        let text = record.to_string();
+        let mut len = text.len().min(self.slot_size - SLOT_HEADER_LEN);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut map = self.map.lock().unwrap_or_else(PoisonError::into_inner);
+        let seq = read_u64(&map[24..32]) + 1;
+        let start = HEADER_LEN + ((seq - 1) as usize % self.slots) * self.slot_size;
+        let slot = &mut map[start..start + self.slot_size];
+        // Invalidate the slot first, so a crash halfway through never leaves
+        // the old sequence number in front of the new text. The sequence
+        // number is written with volatile stores and fenced, so neither store
+        // is dropped or moved across the text by the compiler.
+        write_seq(slot, 0);
+        atomic::fence(Ordering::Release);
+        slot[8..12].copy_from_slice(&(len as u32).to_le_bytes());
+        slot[SLOT_HEADER_LEN..SLOT_HEADER_LEN + len].copy_from_slice(&text.as_bytes()[..len]);
+        atomic::fence(Ordering::Release);
+        write_seq(slot, seq);
+        map[24..32].copy_from_slice(&seq.to_le_bytes());
+    }
+
+    fn flush(&self) {
+        let _ = self
+            .map
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .flush();
+    }
+}
+
+/// The length of a file with `slots` slots of `slot_size` bytes, or `None` if
+/// it does not fit in a `usize`.
+fn file_len(slots: usize, slot_size: usize) -> Option<usize> {
+    slots.checked_mul(slot_size)?.checked_add(HEADER_LEN)
+}
+
+fn write_seq(slot: &mut [u8], seq: u64) {
+    let seq_bytes: &mut [u8; 8] = (&mut slot[..8]).try_into().unwrap();
+    // SAFETY: the pointer comes from a mutable reference, and `[u8; 8]` has no
+    // alignment requirement.
+    unsafe { ptr::write_volatile(seq_bytes, seq.to_le_bytes()) };
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().unwrap())
+}
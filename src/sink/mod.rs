@@ -0,0 +1,236 @@
+//! Destinations for the output of the `dbgonly` macros.
+//!
+//! By default every [`Record`] is printed to [stderr](Stderr), exactly like
+//! `std::dbg!`. A different destination can be installed process-wide with
+//! [`set_sink`].
+//!
+//! Under Miri and sanitizers, installed sinks are bypassed and records are
+//! always written straight to stderr, so that instrumented code stays
+//! runnable without sockets, files or background threads getting in the way.
+
+use std::io::{self, Write};
+use std::sync::{PoisonError, RwLock};
+
+use crate::Record;
+
+mod baseline;
+#[cfg(all(feature = "wasm", target_arch = "wasm32", target_os = "unknown"))]
+mod console;
+mod csv;
+#[cfg(all(feature = "eventlog", windows))]
+mod eventlog;
+mod file;
+pub(crate) mod flush;
+#[cfg(all(feature = "journald", target_os = "linux"))]
+mod journald;
+#[cfg(feature = "log")]
+mod log;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod ndjson;
+#[cfg(all(feature = "oslog", target_vendor = "apple"))]
+mod oslog;
+#[cfg(feature = "otel")]
+mod otel;
+pub(crate) mod ring;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "tracing")]
+mod tracing;
+mod udp;
+mod writer;
+
+pub use baseline::Baseline;
+#[cfg(all(feature = "wasm", target_arch = "wasm32", target_os = "unknown"))]
+pub use console::Console;
+pub use csv::Csv;
+#[cfg(all(feature = "eventlog", windows))]
+pub use eventlog::EventLog;
+pub use file::{to_file, File};
+#[cfg(all(feature = "journald", target_os = "linux"))]
+pub use journald::Journald;
+#[cfg(feature = "log")]
+pub use log::Log;
+#[cfg(feature = "mmap")]
+pub use mmap::MmapRing;
+pub use ndjson::Ndjson;
+#[cfg(all(feature = "oslog", target_vendor = "apple"))]
+pub use oslog::OsLog;
+#[cfg(feature = "otel")]
+pub use otel::Otel;
+pub use ring::Ring;
+#[cfg(feature = "sqlite")]
+pub use sqlite::Sqlite;
+#[cfg(feature = "tracing")]
+pub use tracing::Tracing;
+pub use udp::Udp;
+pub use writer::{set_writer, Writer};
+
+/// A destination for [`Record`]s.
+pub trait Sink: Send + Sync + 'static {
+    /// Writes a single record.
+    fn emit(&self, record: &Record<'_>);
+
+    /// Flushes any buffered records.
+    ///
+    /// This is called when the sink is replaced and when the program panics
+    /// or exits.
+    fn flush(&self) {}
+}
+
+/// The default sink, which prints each record on its own line to stderr.
+///
+/// With the `color` feature, the location, expression and value are shown in
+/// distinct colors, and `Err` and `None` are highlighted, as long as stderr
+/// is a terminal that [supports colors](crate::Terminal::color).
+///
+/// # Panics
+///
+/// Panics if writing to `io::stderr` fails.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stderr;
+
+impl Sink for Stderr {
+    fn emit(&self, record: &Record<'_>) {
+        // The record is rendered up front and written with a single call, so
+        // that multi-line records from several threads do not interleave.
+        // Colors are decided by stderr alone: `terminal` would look at the
+        // installed sink again, while `emit` still holds it.
+        #[cfg(feature = "color")]
+        let mut line = if crate::term::detected().color {
+            record.colored().to_string()
+        } else {
+            record.to_string()
+        };
+        #[cfg(not(feature = "color"))]
+        let mut line = record.to_string();
+        line.push('\n');
+        if let Err(e) = io::stderr().lock().write_all(line.as_bytes()) {
+            panic!("failed printing to stderr: {}", e);
+        }
+    }
+}
+
+/// A sink that writes every record to two other sinks, in order.
+///
+/// ```rust
+/// use dbgonly::sink::{self, Stderr, Tee};
+/// sink::set_sink(Tee(Stderr, Stderr));
+/// # sink::reset_sink();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tee<A, B>(pub A, pub B);
+
+impl<A: Sink, B: Sink> Sink for Tee<A, B> {
+    fn emit(&self, record: &Record<'_>) {
+        self.0.emit(record);
+        self.1.emit(record);
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+        self.1.flush();
+    }
+}
+
+static SINK: RwLock<Option<Box<dyn Sink>>> = RwLock::new(None);
+
+/// The sink used when no other sink is installed.
+#[cfg(feature = "tracing")]
+const DEFAULT: Tracing = Tracing;
+#[cfg(all(feature = "log", not(feature = "tracing")))]
+const DEFAULT: Log = Log;
+#[cfg(all(
+    not(any(feature = "log", feature = "tracing")),
+    feature = "wasm",
+    target_arch = "wasm32",
+    target_os = "unknown"
+))]
+const DEFAULT: Console = Console;
+#[cfg(not(any(
+    feature = "log",
+    feature = "tracing",
+    all(feature = "wasm", target_arch = "wasm32", target_os = "unknown")
+)))]
+const DEFAULT: Stderr = Stderr;
+
+/// Replaces the sink that all `dbgonly` macros write to.
+///
+/// The first call also installs a panic hook and an exit handler that
+/// [`flush`] the active sink, so buffered records are written out even if the
+/// program crashes or calls [`std::process::exit`]. The panic hook runs
+/// before any hook that was installed previously.
+///
+/// ```rust
+/// use dbgonly::sink::{self, Stderr};
+/// sink::set_sink(Stderr);
+/// ```
+pub fn set_sink<S: Sink>(sink: S) {
+    let previous = SINK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .replace(Box::new(sink));
+    if let Some(previous) = previous {
+        previous.flush();
+    }
+    flush::install_guards();
+}
+
+/// Restores the default sink, which is [`Stderr`] unless the `tracing` or
+/// `log` feature is enabled, or the `wasm` feature on
+/// `wasm32-unknown-unknown`. If the `DBGONLY_FILE` environment variable is
+/// set, records go to that [file](to_file) instead.
+pub fn reset_sink() {
+    let previous = SINK.write().unwrap_or_else(PoisonError::into_inner).take();
+    if let Some(previous) = previous {
+        previous.flush();
+    }
+}
+
+/// Flushes any records buffered by the active sink.
+pub fn flush() {
+    if let Some(sink) = &*SINK.read().unwrap_or_else(PoisonError::into_inner) {
+        sink.flush();
+    }
+}
+
+/// Returns whether records are printed to stderr, which is the case if no
+/// sink is installed, `DBGONLY_FILE` is not set and the default sink is
+/// [`Stderr`].
+pub(crate) fn writes_to_stderr() -> bool {
+    !cfg!(any(
+        feature = "log",
+        feature = "tracing",
+        all(
+            feature = "wasm",
+            target_arch = "wasm32",
+            target_os = "unknown"
+        )
+    )) && SINK
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .is_none()
+        && file::from_env().is_none()
+}
+
+pub(crate) fn emit(record: &Record<'_>) {
+    // Leftover probes must not slow down or clutter fuzzing runs, even when
+    // they are active regardless of `debug_assertions` like `dbgtest!`.
+    if cfg!(fuzzing) {
+        return;
+    }
+    if crate::capture::capture_record(record) {
+        return;
+    }
+    if cfg!(any(miri, dbgonly_sanitizer)) {
+        Stderr.emit(record);
+        return;
+    }
+    match &*SINK.read().unwrap_or_else(PoisonError::into_inner) {
+        Some(sink) => sink.emit(record),
+        None => match file::from_env() {
+            Some(file) => file.emit(record),
+            None => DEFAULT.emit(record),
+        },
+    }
+}
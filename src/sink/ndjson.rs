@@ -0,0 +1,136 @@
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
+
+use super::Sink;
+use crate::{record, Record};
+
+/// A sink that writes each record as one JSON object per line, for `jq` and
+/// log collectors.
+///
+/// Every object has the same keys: `file`, `line`, `module`, `expr`,
+/// `value`, `fields`, `context`, `thread` and `timestamp`. The value and the
+/// fields are strings with their `Debug` output, `expr`, `value` and
+/// `context` are `null` when absent, and the timestamp is the record's
+/// [time](Record::time) in seconds since the Unix epoch. Write errors are
+/// ignored:
+///
+/// ```rust
+/// use dbgonly::sink::{self, Ndjson};
+/// sink::set_sink(Ndjson::stderr());
+/// let _ = dbgonly::dbgonly!(vec![1, 2]; unit = "m");
+/// # sink::reset_sink();
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// {"file":"src/main.rs","line":3,"module":"main","expr":"vec![1, 2]","value":"[\n    1,\n    2,\n]","fields":{"unit":"\"m\""},"context":null,"thread":"main","timestamp":1760000000.123}
+/// ```
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[derive(Debug)]
+pub struct Ndjson<W = io::Stderr> {
+    writer: Mutex<W>,
+}
+
+impl Ndjson {
+    /// Creates a sink that writes to stderr.
+    pub fn stderr() -> Self {
+        Ndjson::new(io::stderr())
+    }
+}
+
+impl Default for Ndjson {
+    fn default() -> Self {
+        Ndjson::stderr()
+    }
+}
+
+impl<W: Write + Send + 'static> Ndjson<W> {
+    /// Creates a sink that writes to `writer`.
+    pub fn new(writer: W) -> Self {
+        Ndjson {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: Write + Send + 'static> Sink for Ndjson<W> {
+    fn emit(&self, record: &Record<'_>) {
+        let mut line = String::new();
+        let _ = to_json(&mut line, record);
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = writeln!(writer, "{}", line);
+    }
+
+    fn flush(&self) {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = writer.flush();
+    }
+}
+
+fn to_json(out: &mut String, record: &Record<'_>) -> fmt::Result {
+    out.push_str("{\"file\":");
+    write_str(out, record.file())?;
+    write!(out, ",\"line\":{},\"module\":", record.line())?;
+    write_str(out, record.module_path())?;
+    out.push_str(",\"expr\":");
+    write_opt(out, record.expr())?;
+    out.push_str(",\"value\":");
+    write_opt(out, record.render_value().as_deref())?;
+    out.push_str(",\"fields\":{");
+    for (i, (key, value)) in record.fields().iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_str(out, key)?;
+        out.push(':');
+        write_str(out, &format!("{:?}", value))?;
+    }
+    out.push_str("},\"context\":");
+    write_opt(out, record.context())?;
+    out.push_str(",\"thread\":");
+    write_str(out, record.thread().unwrap_or_else(record::thread_name))?;
+    let timestamp = record
+        .time()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+    write!(out, ",\"timestamp\":{:.3}}}", timestamp)
+}
+
+fn write_opt(out: &mut String, s: Option<&str>) -> fmt::Result {
+    match s {
+        Some(s) => write_str(out, s),
+        None => {
+            out.push_str("null");
+            Ok(())
+        }
+    }
+}
+
+/// Writes a JSON string literal.
+fn write_str(out: &mut String, s: &str) -> fmt::Result {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    Ok(())
+}
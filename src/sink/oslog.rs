@@ -0,0 +1,37 @@
+use oslog::{Level, OsLog as Logger};
+
+use super::Sink;
+use crate::Record;
+
+/// A sink that forwards records to Apple's unified logging system.
+///
+/// Records are logged at debug level under the given subsystem and category,
+/// so they can be inspected in Console.app or with `log stream`, even for
+/// applications launched from Finder that have no visible stderr:
+///
+/// ```text,ignore
+/// log stream --level debug --predicate 'subsystem == "com.example.app"'
+/// ```
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, OsLog};
+/// sink::set_sink(OsLog::new("com.example.app", "dbgonly"));
+/// ```
+pub struct OsLog {
+    log: Logger,
+}
+
+impl OsLog {
+    /// Creates a log handle for `subsystem` and `category`.
+    pub fn new(subsystem: &str, category: &str) -> Self {
+        OsLog {
+            log: Logger::new(subsystem, category),
+        }
+    }
+}
+
+impl Sink for OsLog {
+    fn emit(&self, record: &Record<'_>) {
+        self.log.with_level(Level::Debug, &record.to_string());
+    }
+}
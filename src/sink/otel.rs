@@ -0,0 +1,70 @@
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, LoggerProvider, Severity};
+
+use super::Sink;
+use crate::Record;
+
+/// A sink that converts records into OpenTelemetry log records.
+///
+/// Every record is emitted at [`Severity::Debug`] through a logger named
+/// `dbgonly` obtained from the given provider, with the rendered record as
+/// its body and the call site as attributes (`code.file.path`,
+/// `code.line.number`, `dbg.module`, `dbg.expr`, `dbg.value`). Key-value
+/// fields and the thread's context are attached as attributes as well, so
+/// they can be queried in whatever backend collects the OTLP export.
+///
+/// ```rust,ignore
+/// use dbgonly::sink::{self, Otel};
+/// let provider = opentelemetry_sdk::logs::SdkLoggerProvider::builder()
+///     .with_batch_exporter(exporter)
+///     .build();
+/// sink::set_sink(Otel::new(&provider));
+/// ```
+#[derive(Debug)]
+pub struct Otel<L> {
+    logger: L,
+}
+
+impl<L: Logger> Otel<L> {
+    /// Creates a sink that emits through a logger from `provider`.
+    pub fn new<P: LoggerProvider<Logger = L>>(provider: &P) -> Self {
+        Otel {
+            logger: provider.logger("dbgonly"),
+        }
+    }
+}
+
+impl<L: Logger + Send + Sync + 'static> Sink for Otel<L> {
+    fn emit(&self, record: &Record<'_>) {
+        if !self
+            .logger
+            .event_enabled(Severity::Debug, record.module_path(), None)
+        {
+            return;
+        }
+        let mut log = self.logger.create_log_record();
+        log.set_timestamp(record.time());
+        log.set_target(record.module_path());
+        log.set_severity_number(Severity::Debug);
+        log.set_severity_text("DEBUG");
+        log.set_body(AnyValue::from(record.to_string()));
+        log.add_attribute("code.file.path", record.file());
+        log.add_attribute("code.line.number", i64::from(record.line()));
+        log.add_attribute("dbg.module", record.module_path());
+        if let Some(expr) = record.expr() {
+            log.add_attribute("dbg.expr", expr.to_owned());
+        }
+        if let Some(value) = record.render_value() {
+            log.add_attribute("dbg.value", value);
+        }
+        for (key, value) in record.fields() {
+            log.add_attribute(*key, format!("{:?}", value));
+        }
+        if let Some(context) = record.context() {
+            log.add_attribute("dbg.context", context.to_owned());
+        }
+        if crate::show_run_id() {
+            log.add_attribute("dbg.run_id", crate::run_id());
+        }
+        self.logger.emit(log);
+    }
+}
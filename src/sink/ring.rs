@@ -0,0 +1,200 @@
+use std::io::{self, Write};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, TryLockError, Weak};
+
+use super::Sink;
+use crate::Record;
+
+static RINGS: Mutex<Vec<Weak<Inner>>> = Mutex::new(Vec::new());
+
+/// A sink that keeps the most recent records in memory.
+///
+/// Once the ring buffer is full, each new record replaces the oldest one.
+/// `Ring` is a cheap handle: clones share the same buffer, so one clone can
+/// be installed as the sink while another one is kept around to inspect the
+/// records later. All live ring buffers are also printed by
+/// [`dump_state`](crate::dump_state), and when the program panics if
+/// [`install_panic_hook`](crate::install_panic_hook) was called.
+///
+/// ```rust
+/// use dbgonly::sink::{self, Ring};
+/// let ring = Ring::new(1000);
+/// sink::set_sink(ring.clone());
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// # sink::reset_sink();
+/// for record in ring.records() {
+///     println!("{}", record);
+/// }
+/// ```
+///
+/// The buffer is lock-free, so records can be added and read from any
+/// thread, including from a panic hook while another thread was interrupted
+/// halfway through adding one.
+#[derive(Clone, Debug)]
+pub struct Ring {
+    inner: Arc<Inner>,
+}
+
+/// A fixed number of slots, each holding the latest record written to it.
+///
+/// Writers take the next sequence number and swap their record into the
+/// slot it maps to. Readers briefly swap a record out of its slot to copy it,
+/// so that no writer frees it in the meantime, and then put it back. Either
+/// way, a slot keeps the latest of the records that meet in it.
+#[derive(Debug)]
+struct Inner {
+    slots: Box<[AtomicPtr<Entry>]>,
+    next: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    seq: u64,
+    text: String,
+}
+
+impl Ring {
+    /// Creates a ring buffer that holds up to `capacity` records.
+    pub fn new(capacity: usize) -> Self {
+        let inner = Arc::new(Inner {
+            slots: (0..capacity)
+                .map(|_| AtomicPtr::new(ptr::null_mut()))
+                .collect(),
+            next: AtomicU64::new(0),
+        });
+        let mut rings = RINGS.lock().unwrap_or_else(PoisonError::into_inner);
+        rings.retain(|ring| ring.strong_count() > 0);
+        rings.push(Arc::downgrade(&inner));
+        Ring { inner }
+    }
+
+    /// Returns the buffered records, oldest first.
+    pub fn records(&self) -> Vec<String> {
+        self.inner.snapshot()
+    }
+
+    /// Removes all buffered records.
+    pub fn clear(&self) {
+        for slot in self.inner.slots.iter() {
+            free(slot.swap(ptr::null_mut(), Ordering::AcqRel));
+        }
+    }
+
+    /// Writes the buffered records to `w`, oldest first.
+    pub fn dump(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.inner.dump(w)
+    }
+}
+
+impl Inner {
+    fn push(&self, text: String) {
+        let seq = self.next.fetch_add(1, Ordering::Relaxed);
+        let slot = &self.slots[(seq % self.slots.len() as u64) as usize];
+        install(slot, Box::into_raw(Box::new(Entry { seq, text })));
+    }
+
+    fn snapshot(&self) -> Vec<String> {
+        let mut entries = Vec::new();
+        for slot in self.slots.iter() {
+            let entry = slot.swap(ptr::null_mut(), Ordering::AcqRel);
+            if entry.is_null() {
+                continue;
+            }
+            // SAFETY: the entry was swapped out of its slot, so no other
+            // thread can free it until it is put back.
+            let (seq, text) = unsafe { ((*entry).seq, (*entry).text.clone()) };
+            entries.push((seq, text));
+            install(slot, entry);
+        }
+        entries.sort_unstable_by_key(|&(seq, _)| seq);
+        entries.into_iter().map(|(_, text)| text).collect()
+    }
+
+    fn dump(&self, w: &mut dyn Write) -> io::Result<()> {
+        let records = self.snapshot();
+        writeln!(
+            w,
+            "[dbgonly] ring buffer ({} of {} records):",
+            records.len(),
+            self.slots.len()
+        )?;
+        for record in records.iter() {
+            writeln!(w, "{}", record)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        for slot in self.slots.iter_mut() {
+            free(*slot.get_mut());
+        }
+    }
+}
+
+/// Puts an entry that is owned by the current thread into its slot, unless
+/// the slot holds a later record by now.
+fn install(slot: &AtomicPtr<Entry>, mut entry: *mut Entry) {
+    loop {
+        let old = slot.swap(entry, Ordering::AcqRel);
+        // SAFETY: both entries were swapped out of the slot by this thread,
+        // so no other thread can free them.
+        if old.is_null() || unsafe { (*old).seq < (*entry).seq } {
+            free(old);
+            return;
+        }
+        // A later record got there first, so it is put back. If the slot
+        // was written to again in the meantime, the entry that was swapped
+        // in is now owned by that writer, and the later record is installed
+        // again instead.
+        match slot.compare_exchange(entry, old, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => {
+                free(entry);
+                return;
+            }
+            Err(_) => entry = old,
+        }
+    }
+}
+
+/// Frees an entry that was swapped out of its slot, if any.
+fn free(entry: *mut Entry) {
+    if !entry.is_null() {
+        // SAFETY: entries are created with `Box::into_raw` and freed only by
+        // the thread that swapped them out of their slot.
+        drop(unsafe { Box::from_raw(entry) });
+    }
+}
+
+impl Sink for Ring {
+    fn emit(&self, record: &Record<'_>) {
+        if self.inner.slots.is_empty() {
+            return;
+        }
+        self.inner.push(record.to_string());
+    }
+}
+
+/// Dumps every live ring buffer to `w`.
+///
+/// This never blocks, so that it can be called from a panic hook, even if the
+/// panic interrupted the creation of a ring buffer.
+pub(crate) fn dump_all(w: &mut dyn Write) -> io::Result<()> {
+    let rings: Vec<_> = match RINGS.try_lock() {
+        Ok(rings) => rings.iter().filter_map(Weak::upgrade).collect(),
+        Err(TryLockError::Poisoned(rings)) => rings
+            .into_inner()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect(),
+        Err(TryLockError::WouldBlock) => {
+            return writeln!(w, "[dbgonly] ring buffers are busy, cannot dump them");
+        }
+    };
+    for ring in rings {
+        ring.dump(w)?;
+    }
+    Ok(())
+}
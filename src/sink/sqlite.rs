@@ -0,0 +1,110 @@
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::SystemTime;
+
+use rusqlite::{params, Connection};
+
+use super::Sink;
+use crate::Record;
+
+/// A sink that appends records to a table in a local SQLite database.
+///
+/// The `records` table is created if it does not exist yet, with one row per
+/// record:
+///
+/// | column    | type    | contents                                             |
+/// |-----------|---------|------------------------------------------------------|
+/// | `ts`      | REAL    | when it was emitted, in seconds since the Unix epoch |
+/// | `run_id`  | TEXT    | the [run identifier](crate::run_id)                  |
+/// | `thread`  | TEXT    | the name or id of the emitting thread                |
+/// | `file`    | TEXT    | the source file of the call site                     |
+/// | `line`    | INTEGER | the line of the call site                            |
+/// | `module`  | TEXT    | the module path of the call site                     |
+/// | `expr`    | TEXT    | the source code of the expression                    |
+/// | `value`   | TEXT    | the rendered value                                   |
+/// | `fields`  | TEXT    | the key-value fields, as `key=value` text            |
+/// | `context` | TEXT    | the thread's context                                 |
+///
+/// so a long debugging session can be explored with SQL afterwards:
+///
+/// ```text,ignore
+/// sqlite3 debug.db "SELECT line, count(*) FROM records GROUP BY file, line"
+/// ```
+///
+/// Records that cannot be inserted are dropped.
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, Sqlite};
+/// sink::set_sink(Sqlite::open("debug.db").expect("cannot open database"));
+/// ```
+#[derive(Debug)]
+pub struct Sqlite {
+    connection: Mutex<Connection>,
+}
+
+impl Sqlite {
+    /// Opens or creates the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS records (
+                ts REAL NOT NULL,
+                run_id TEXT NOT NULL,
+                thread TEXT NOT NULL,
+                file TEXT NOT NULL,
+                line INTEGER NOT NULL,
+                module TEXT NOT NULL,
+                expr TEXT,
+                value TEXT,
+                fields TEXT,
+                context TEXT
+            )",
+        )?;
+        Ok(Sqlite {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl Sink for Sqlite {
+    fn emit(&self, record: &Record<'_>) {
+        let ts = record
+            .time()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        let current = thread::current();
+        let thread = match current.name() {
+            Some(name) => name.to_owned(),
+            None => format!("{:?}", current.id()),
+        };
+        let fields = (!record.fields().is_empty()).then(|| {
+            record
+                .fields()
+                .iter()
+                .map(|(key, value)| format!("{}={:?}", key, value))
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        let connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let _ = connection.execute(
+            "INSERT INTO records (ts, run_id, thread, file, line, module, expr, value, fields, context)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                ts,
+                crate::run_id(),
+                thread,
+                record.file(),
+                record.line(),
+                record.module_path(),
+                record.expr(),
+                record.render_value(),
+                fields,
+                record.context(),
+            ],
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use tracing_core::callsite::{self, Callsite as _, Identifier};
+use tracing_core::field::{FieldSet, Value};
+use tracing_core::metadata::Kind;
+use tracing_core::subscriber::Interest;
+use tracing_core::{dispatcher, Event, Level, LevelFilter, Metadata};
+
+use super::Sink;
+use crate::Record;
+
+const FIELDS: &[&str] = &["message"];
+
+/// The tracing callsites of the `dbgonly` call sites that have emitted an
+/// event, created on first use.
+static SITES: Mutex<BTreeMap<(&'static str, u32, &'static str), &'static Site>> =
+    Mutex::new(BTreeMap::new());
+
+/// A sink that emits every record as a `DEBUG` event through the
+/// [`tracing`](https://docs.rs/tracing) facade.
+///
+/// Every call site becomes a tracing callsite of its own, with the module
+/// path of the call site as its target and its file and line, so events are
+/// filtered and formatted by the installed subscriber like those of
+/// `tracing::debug!`. The `message` field is the record rendered like on
+/// stderr.
+///
+/// With the `tracing` feature, this is also where records go when no other
+/// sink is installed.
+///
+/// ```rust
+/// use dbgonly::sink::{self, Tracing};
+/// sink::set_sink(Tracing);
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// # sink::reset_sink();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tracing;
+
+impl Sink for Tracing {
+    fn emit(&self, record: &Record<'_>) {
+        if Level::DEBUG > LevelFilter::current() {
+            return;
+        }
+        let metadata = site(record).metadata();
+        dispatcher::get_default(|dispatch| {
+            if !dispatch.enabled(metadata) {
+                return;
+            }
+            let fields = metadata.fields();
+            let Some(message) = fields.field("message") else {
+                return;
+            };
+            let args = format_args!("{}", record);
+            let values = [(&message, Some(&args as &dyn Value))];
+            dispatch.event(&Event::new(metadata, &fields.value_set(&values)));
+        });
+    }
+}
+
+struct Site {
+    metadata: OnceLock<Metadata<'static>>,
+}
+
+impl callsite::Callsite for Site {
+    fn set_interest(&self, _: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'static> {
+        self.metadata
+            .get()
+            .expect("metadata is set before registration")
+    }
+}
+
+fn site(record: &Record<'_>) -> &'static Site {
+    let key = (record.file(), record.line(), record.module_path());
+    let mut sites = SITES.lock().unwrap_or_else(PoisonError::into_inner);
+    sites.entry(key).or_insert_with(|| {
+        let site: &'static Site = Box::leak(Box::new(Site {
+            metadata: OnceLock::new(),
+        }));
+        let target = match record.module_path() {
+            "" => "dbgonly",
+            module_path => module_path,
+        };
+        let _ = site.metadata.set(Metadata::new(
+            "dbgonly",
+            target,
+            Level::DEBUG,
+            Some(record.file()),
+            Some(record.line()),
+            Some(target),
+            FieldSet::new(FIELDS, Identifier(site)),
+            Kind::EVENT,
+        ));
+        callsite::register(site);
+        site
+    })
+}
@@ -0,0 +1,76 @@
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+
+use super::Sink;
+use crate::Record;
+
+/// A sink that sends every record as a UDP datagram.
+///
+/// This is meant to be paired with the `dbgonly-tail` viewer (installed with
+/// `cargo install dbgonly --features bin`), which listens on
+/// `127.0.0.1:7777` by default and can colorize, filter and pause the
+/// stream:
+///
+/// ```rust,no_run
+/// use dbgonly::sink::{self, Udp};
+/// sink::set_sink(Udp::connect("127.0.0.1:7777").unwrap());
+/// ```
+///
+/// Each datagram consists of a header line with the tab-separated file, line,
+/// module path and context of the record, followed by the rendered record.
+/// Records that cannot be sent, for example because they exceed the maximum
+/// datagram size, are dropped.
+#[derive(Debug)]
+pub struct Udp {
+    socket: UdpSocket,
+}
+
+impl Udp {
+    /// Creates a sink that sends to `addr`.
+    ///
+    /// If `addr` resolves to several addresses, the first one that can be
+    /// connected to is used.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let mut last_error = None;
+        for addr in addr.to_socket_addrs()? {
+            match connect_to(addr) {
+                Ok(socket) => return Ok(Udp { socket }),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any addresses",
+            )
+        }))
+    }
+}
+
+// The local socket has to be of the same address family as the target.
+fn connect_to(addr: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = if addr.is_ipv6() {
+        UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0))?
+    } else {
+        UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?
+    };
+    socket.connect(addr)?;
+    Ok(socket)
+}
+
+impl Sink for Udp {
+    fn emit(&self, record: &Record<'_>) {
+        let datagram = format!(
+            "{}\t{}\t{}\t{}\n{}",
+            record.file(),
+            record.line(),
+            record.module_path(),
+            record
+                .context()
+                .unwrap_or_default()
+                .replace(['\t', '\n'], " "),
+            record
+        );
+        let _ = self.socket.send(datagram.as_bytes());
+    }
+}
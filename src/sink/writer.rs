@@ -0,0 +1,68 @@
+use std::io::Write;
+use std::sync::{Mutex, PoisonError};
+
+use super::Sink;
+use crate::Record;
+
+/// A sink that prints each record on its own line to any [`Write`]
+/// implementation, such as a socket, a pipe or an in-memory buffer.
+///
+/// Records are formatted exactly like on stderr. Write errors are ignored,
+/// so a closed socket does not bring the program down with it:
+///
+/// ```rust,no_run
+/// use std::net::TcpStream;
+/// let stream = TcpStream::connect("127.0.0.1:9000").unwrap();
+/// dbgonly::set_writer(stream);
+/// ```
+///
+/// [`set_writer`](crate::set_writer) installs a `Writer` in one call.
+#[derive(Debug, Default)]
+pub struct Writer<W> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send + 'static> Writer<W> {
+    /// Creates a sink that writes to `writer`.
+    pub fn new(writer: W) -> Self {
+        Writer {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: Write + Send + 'static> Sink for Writer<W> {
+    fn emit(&self, record: &Record<'_>) {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = writeln!(writer, "{}", record);
+    }
+
+    fn flush(&self) {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = writer.flush();
+    }
+}
+
+/// Redirects the output of all `dbgonly` macros to `writer`.
+///
+/// This is a shorthand for installing a [`Writer`] sink with
+/// [`set_sink`](super::set_sink); the output can be sent back to stderr with
+/// [`reset_sink`](super::reset_sink).
+///
+/// ```rust
+/// use std::io;
+/// dbgonly::set_writer(io::sink());
+/// let _ = dbgonly::dbgonly!(1 + 1);
+/// //      ^-- prints nothing
+/// # dbgonly::sink::reset_sink();
+/// ```
+pub fn set_writer(writer: impl Write + Send + 'static) {
+    super::set_sink(Writer::new(writer));
+}
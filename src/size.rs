@@ -0,0 +1,228 @@
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::mem;
+
+/// Prints the memory footprint of a value and returns the value. This version
+/// of the macro will print nothing and be optimized out in release builds.
+///
+/// The size and alignment of the value itself are always printed. For
+/// `Vec`, `String`, `VecDeque`, `HashMap`, `HashSet`, `BinaryHeap` and
+/// `Box`, the length, the capacity and the size of the heap allocation are
+/// printed as well, which makes over-allocated buffers easy to spot:
+///
+/// ```rust
+/// use dbgonly::dbg_size;
+/// let mut samples: Vec<u64> = Vec::with_capacity(1024);
+/// samples.extend([1, 2, 3]);
+/// let samples = dbg_size!(samples);
+/// //            ^-- prints: [src/main.rs:4] samples = 24 bytes, align 8, len 3, capacity 1024, heap 8192 bytes
+/// # assert_eq!(samples.len(), 3);
+/// ```
+///
+/// The value does not have to implement `Debug`, since it is not printed. For
+/// a reference to one of the containers above, the footprint of the container
+/// it points to is printed.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_size {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!("{}", {
+                        #[allow(unused_imports)]
+                        use $crate::__private::{ContainerSize as _, PlainSize as _};
+                        (&$crate::__private::SizeOf(&tmp)).footprint()
+                    })),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_size!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_size {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_size!($val)),+,)
+    };
+}
+
+pub struct SizeOf<'a, T: ?Sized>(pub &'a T);
+
+/// The footprint of a value, as printed by [`dbg_size!`](crate::dbg_size).
+pub struct Footprint {
+    size: usize,
+    align: usize,
+    container: Option<Container>,
+}
+
+struct Container {
+    len: Option<usize>,
+    capacity: Option<usize>,
+    heap: Option<usize>,
+}
+
+impl fmt::Display for Footprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes, align {}", self.size, self.align)?;
+        if let Some(container) = &self.container {
+            if let Some(len) = container.len {
+                write!(f, ", len {}", len)?;
+            }
+            if let Some(capacity) = container.capacity {
+                write!(f, ", capacity {}", capacity)?;
+            }
+            if let Some(heap) = container.heap {
+                write!(f, ", heap {} bytes", heap)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Any value, which only has a size and alignment.
+pub trait PlainSize {
+    fn footprint(&self) -> Footprint;
+}
+
+impl<T: ?Sized> PlainSize for &SizeOf<'_, T> {
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            size: mem::size_of_val(self.0),
+            align: mem::align_of_val(self.0),
+            container: None,
+        }
+    }
+}
+
+/// A container, which also has a length and usually a capacity. This is
+/// preferred over [`PlainSize`] by method resolution, since it is
+/// implemented for `SizeOf` instead of `&SizeOf`.
+pub trait ContainerSize {
+    fn footprint(&self) -> Footprint;
+}
+
+/// Types whose footprint includes a length, a capacity and a heap allocation.
+trait Contents {
+    fn contents(&self) -> Container;
+
+    fn footprint(&self) -> Footprint {
+        Footprint {
+            size: mem::size_of_val(self),
+            align: mem::align_of_val(self),
+            container: Some(self.contents()),
+        }
+    }
+}
+
+impl<T> Contents for Vec<T> {
+    fn contents(&self) -> Container {
+        Container {
+            len: Some(self.len()),
+            capacity: Some(self.capacity()),
+            heap: Some(self.capacity() * mem::size_of::<T>()),
+        }
+    }
+}
+
+impl Contents for String {
+    fn contents(&self) -> Container {
+        Container {
+            len: Some(self.len()),
+            capacity: Some(self.capacity()),
+            heap: Some(self.capacity()),
+        }
+    }
+}
+
+impl<T> Contents for VecDeque<T> {
+    fn contents(&self) -> Container {
+        Container {
+            len: Some(self.len()),
+            capacity: Some(self.capacity()),
+            heap: Some(self.capacity() * mem::size_of::<T>()),
+        }
+    }
+}
+
+impl<T> Contents for BinaryHeap<T> {
+    fn contents(&self) -> Container {
+        Container {
+            len: Some(self.len()),
+            capacity: Some(self.capacity()),
+            heap: Some(self.capacity() * mem::size_of::<T>()),
+        }
+    }
+}
+
+// The layout of the hash tables is an implementation detail, so their heap
+// size is not estimated.
+impl<K, V, S> Contents for HashMap<K, V, S> {
+    fn contents(&self) -> Container {
+        Container {
+            len: Some(self.len()),
+            capacity: Some(self.capacity()),
+            heap: None,
+        }
+    }
+}
+
+impl<T, S> Contents for HashSet<T, S> {
+    fn contents(&self) -> Container {
+        Container {
+            len: Some(self.len()),
+            capacity: Some(self.capacity()),
+            heap: None,
+        }
+    }
+}
+
+impl<T: ?Sized> Contents for Box<T> {
+    fn contents(&self) -> Container {
+        Container {
+            len: None,
+            capacity: None,
+            heap: Some(mem::size_of_val(&**self)),
+        }
+    }
+}
+
+// References print the footprint of the container they point to, since the
+// size of the reference itself is rarely what is being asked about.
+impl<C: Contents + ?Sized> Contents for &C {
+    fn footprint(&self) -> Footprint {
+        (**self).footprint()
+    }
+
+    fn contents(&self) -> Container {
+        (**self).contents()
+    }
+}
+
+impl<C: Contents + ?Sized> Contents for &mut C {
+    fn footprint(&self) -> Footprint {
+        (**self).footprint()
+    }
+
+    fn contents(&self) -> Container {
+        (**self).contents()
+    }
+}
+
+impl<C: Contents> ContainerSize for SizeOf<'_, C> {
+    fn footprint(&self) -> Footprint {
+        self.0.footprint()
+    }
+}
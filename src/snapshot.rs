@@ -0,0 +1,44 @@
+/// Asserts the value of a given expression against an [insta] snapshot in
+/// tests, and prints it like [`dbgonly!`] everywhere else.
+///
+/// This bridges exploratory debugging and locking in expected output: a probe
+/// that was added to look at a value can be left in place, and once the
+/// invoking crate is compiled with `cfg(test)` it turns into an
+/// `insta::assert_debug_snapshot!`, reviewed and updated with
+/// `cargo insta review` as usual. The value is returned in both cases.
+///
+/// ```rust
+/// use dbgonly::dbg_snapshot;
+/// fn normalize(path: &str) -> String {
+///     dbg_snapshot!(path.replace('\\', "/"))
+/// }
+/// # assert_eq!(normalize("a\\b"), "a/b");
+/// ```
+///
+/// Snapshots are named after the function that invokes the macro, like those
+/// of the insta macros themselves.
+///
+/// [insta]: https://insta.rs
+/// [`dbgonly!`]: crate::dbgonly
+#[macro_export]
+macro_rules! dbg_snapshot {
+    // NOTE: As in `dbgtest!`, the `cfg` attributes are evaluated in the
+    // invoking crate.
+    ($val:expr $(,)?) => {
+        match () {
+            #[cfg(test)]
+            () => match $val {
+                tmp => {
+                    $crate::__private::insta::assert_debug_snapshot!(
+                        ::core::option::Option::None::<&str>,
+                        tmp,
+                        stringify!($val)
+                    );
+                    tmp
+                }
+            },
+            #[cfg(not(test))]
+            () => $crate::dbgonly!($val),
+        }
+    };
+}
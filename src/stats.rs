@@ -0,0 +1,215 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::{sink, Callsite};
+
+static STATS: Mutex<Vec<&'static Stats>> = Mutex::new(Vec::new());
+static REPORT_AT_EXIT: AtomicBool = AtomicBool::new(false);
+
+/// Records a numeric sample for the statistics of this call site and returns
+/// it. This version of the macro will record nothing and be optimized out in
+/// release builds.
+///
+/// Nothing is printed when a sample is recorded. The count, minimum, maximum
+/// and mean of the samples of all call sites are printed by
+/// [`report_stats`](crate::report_stats) and
+/// [`dump_state`](crate::dump_state), which shows the distribution of a value
+/// across a run instead of thousands of separate lines:
+///
+/// ```rust
+/// use dbgonly::dbg_stats;
+/// let mut steps = 0;
+/// for n in 1..=100u64 {
+///     let mut x = n;
+///     while x != 1 {
+///         x = if x % 2 == 0 { x / 2 } else { 3 * x + 1 };
+///         steps += 1;
+///     }
+///     dbg_stats!("collatz steps", steps);
+///     steps = 0;
+/// }
+/// dbgonly::report_stats();
+/// # assert_eq!(steps, 0);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [dbgonly] stats (1 call site):
+/// [src/main.rs:8] collatz steps: count 100, min 0, max 118, mean 31.42
+/// ```
+///
+/// Samples can be of any primitive numeric type and are converted to `f64`
+/// with `as`. NaN and infinite samples are only counted, as `non-finite`, so
+/// that a single one does not spoil the other statistics.
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_stats {
+    ($label:literal, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                static STATS: $crate::__private::Stats = $crate::__private::Stats::new(
+                    $crate::Callsite::new(
+                        file!(),
+                        line!(),
+                        module_path!(),
+                        ::core::option::Option::Some($label),
+                    )
+                    .silenced_if($crate::__silenced!()),
+                );
+                STATS.record(tmp as f64);
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_stats {
+    ($label:literal, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _: &str = $label;
+                    let _ = tmp as f64;
+                }
+                tmp
+            }
+        }
+    };
+}
+
+/// The samples of a [`dbg_stats!`](crate::dbg_stats) call site.
+pub struct Stats {
+    callsite: Callsite,
+    summary: Mutex<Summary>,
+}
+
+#[derive(Clone, Copy)]
+struct Summary {
+    /// The number of finite samples, which the other statistics are over.
+    count: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    non_finite: u64,
+}
+
+impl Stats {
+    pub const fn new(callsite: Callsite) -> Self {
+        Stats {
+            callsite,
+            summary: Mutex::new(Summary {
+                count: 0,
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+                sum: 0.0,
+                non_finite: 0,
+            }),
+        }
+    }
+
+    pub fn record(&'static self, sample: f64) {
+        if !self.callsite.is_enabled() {
+            return;
+        }
+        let first = {
+            let mut summary = self.summary.lock().unwrap_or_else(PoisonError::into_inner);
+            if sample.is_finite() {
+                summary.count += 1;
+                summary.min = summary.min.min(sample);
+                summary.max = summary.max.max(sample);
+                summary.sum += sample;
+            } else {
+                summary.non_finite += 1;
+            }
+            summary.count + summary.non_finite == 1
+        };
+        if first {
+            STATS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(self);
+        }
+    }
+}
+
+/// Prints the statistics of all [`dbg_stats!`](crate::dbg_stats) call sites
+/// that were reached to stderr, in order of their location.
+pub fn report_stats() {
+    let mut stderr = io::stderr().lock();
+    let _ = write_stats(&mut stderr);
+    let _ = stderr.flush();
+}
+
+/// Prints the statistics with [`report_stats`] once, when the program exits,
+/// including when a panic unwinds out of `main`.
+///
+/// The report is printed by an exit handler, so only on Unix and Windows, and
+/// not when the program is aborted, for example by a panic with
+/// `panic = "abort"`. Call [`report_stats`] directly on other targets.
+///
+/// ```rust
+/// dbgonly::report_stats_at_exit();
+/// ```
+pub fn report_stats_at_exit() {
+    REPORT_AT_EXIT.store(true, Ordering::Relaxed);
+    sink::flush::install_guards();
+}
+
+#[cfg(any(unix, windows))]
+pub(crate) fn report_at_exit() {
+    if REPORT_AT_EXIT.swap(false, Ordering::Relaxed) {
+        report_stats();
+    }
+}
+
+pub(crate) fn write_stats(w: &mut dyn Write) -> io::Result<()> {
+    let mut stats: Vec<_> = STATS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .map(|stats| {
+            let summary = *stats.summary.lock().unwrap_or_else(PoisonError::into_inner);
+            (&stats.callsite, summary)
+        })
+        .collect();
+    if stats.is_empty() {
+        return Ok(());
+    }
+    stats.sort_by(|a, b| (a.0.file(), a.0.line()).cmp(&(b.0.file(), b.0.line())));
+    writeln!(
+        w,
+        "[dbgonly] stats ({} call site{}):",
+        stats.len(),
+        if stats.len() == 1 { "" } else { "s" }
+    )?;
+    for (callsite, summary) in stats {
+        write!(
+            w,
+            "[{}:{}] {}: count {}",
+            callsite.file(),
+            callsite.line(),
+            callsite.expr().unwrap_or_default(),
+            summary.count
+        )?;
+        if summary.count > 0 {
+            write!(
+                w,
+                ", min {}, max {}, mean {:.2}",
+                summary.min,
+                summary.max,
+                summary.sum / summary.count as f64
+            )?;
+        }
+        if summary.non_finite > 0 {
+            write!(w, ", non-finite {}", summary.non_finite)?;
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,329 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static EDGE: AtomicUsize = AtomicUsize::new(3);
+
+/// Prints the length and the first and last few elements of a slice and
+/// returns it. This version of the macro will print nothing and be optimized
+/// out in release builds.
+///
+/// The expression can be anything that implements `AsRef<[T]>`, such as
+/// `Vec<T>`, arrays or slices. Instead of the whole contents, only the first
+/// and last elements are printed, three of each by default. The number can
+/// be given as a second argument or changed for all call sites with
+/// [`set_slice_edge`](crate::set_slice_edge):
+///
+/// ```rust
+/// use dbgonly::dbg_slice;
+/// let samples: Vec<u32> = (0..1_000_000).collect();
+/// let samples = dbg_slice!(samples);
+/// //            ^-- prints: [src/main.rs:3] samples = len 1000000: [0, 1, 2, …, 999997, 999998, 999999]
+/// let samples = dbg_slice!(samples, 1);
+/// //            ^-- prints: [src/main.rs:5] samples = len 1000000: [0, …, 999999]
+/// # assert_eq!(samples.len(), 1_000_000);
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_slice {
+    ($val:expr $(,)?) => {
+        $crate::dbg_slice!($val, $crate::slice_edge())
+    };
+    ($val:expr, $edge:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::SliceSummary(
+                            ::core::convert::AsRef::<[_]>::as_ref(&tmp),
+                            $edge
+                        )
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_slice {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($val:expr, $edge:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _: usize = $edge;
+                }
+                tmp
+            }
+        }
+    };
+}
+
+/// Sets the number of elements [`dbg_slice!`](crate::dbg_slice) prints from
+/// each end of a slice when no number is given at the call site.
+///
+/// The default is 3.
+pub fn set_slice_edge(edge: usize) {
+    EDGE.store(edge, Ordering::Relaxed);
+}
+
+/// Returns the number of elements printed from each end of a slice.
+pub fn slice_edge() -> usize {
+    EDGE.load(Ordering::Relaxed)
+}
+
+pub struct SliceSummary<'a, T>(pub &'a [T], pub usize);
+
+impl<T: fmt::Debug> fmt::Display for SliceSummary<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let SliceSummary(slice, edge) = *self;
+        write!(f, "len {}: [", slice.len())?;
+        if slice.len() <= edge.saturating_mul(2) {
+            return write_list(f, slice.iter()).and_then(|()| f.write_char(']'));
+        }
+        write_list(f, slice[..edge].iter())?;
+        if edge > 0 {
+            f.write_str(", ")?;
+        }
+        f.write_char('…')?;
+        if edge > 0 {
+            f.write_str(", ")?;
+        }
+        write_list(f, slice[slice.len() - edge..].iter())?;
+        f.write_char(']')
+    }
+}
+
+fn write_list<T: fmt::Debug>(
+    f: &mut fmt::Formatter<'_>,
+    items: impl Iterator<Item = T>,
+) -> fmt::Result {
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{:?}", item)?;
+    }
+    Ok(())
+}
+
+/// The number of entries [`dbg_map!`](crate::dbg_map) prints.
+const MAP_SAMPLES: usize = 5;
+
+/// Prints the number of entries and a few sample entries of a map or set and
+/// returns it. This version of the macro will print nothing and be optimized
+/// out in release builds.
+///
+/// The expression can be a `HashMap`, `BTreeMap`, `HashSet` or `BTreeSet`, or
+/// a reference to one. The sample entries are the first five when sorted by
+/// the `Debug` output of their keys, so the output is the same between runs
+/// even for hashed collections:
+///
+/// ```rust
+/// use dbgonly::dbg_map;
+/// use std::collections::HashMap;
+/// let scores: HashMap<String, u32> = (0..1000).map(|i| (format!("player{:03}", i), i)).collect();
+/// let scores = dbg_map!(scores);
+/// //           ^-- prints: [src/main.rs:4] scores = len 1000: {"player000": 0, "player001": 1, "player002": 2, "player003": 3, "player004": 4, …}
+/// # assert_eq!(scores.len(), 1000);
+/// ```
+///
+/// With `stats` as a second argument, the keys are also counted by their
+/// kind: the enum variant or type name at the start of their `Debug` output,
+/// or `string`, `number`, `tuple` or `list`:
+///
+/// ```rust
+/// use dbgonly::dbg_map;
+/// use std::collections::BTreeSet;
+/// #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// enum Entity { Player(u32), Enemy(u32) }
+/// let alive: BTreeSet<Entity> = (0..10).map(Entity::Enemy).chain([Entity::Player(0)]).collect();
+/// dbg_map!(&alive, stats);
+/// //  ^-- prints: [src/main.rs:6] &alive = len 11: {Enemy(0), Enemy(1), Enemy(2), Enemy(3), Enemy(4), …} keys: Enemy 10, Player 1
+/// ```
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_map {
+    (@summary $val:expr, $stats:expr) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::MapSummary(&tmp, $stats)
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+    ($val:expr $(,)?) => {
+        $crate::dbg_map!(@summary $val, false)
+    };
+    ($val:expr, stats $(,)?) => {
+        $crate::dbg_map!(@summary $val, true)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_map {
+    ($val:expr $(,)? $(, stats $(,)?)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+}
+
+/// The maps and sets accepted by [`dbg_map!`](crate::dbg_map).
+pub trait Collection {
+    /// The number of entries.
+    fn count(&self) -> usize;
+
+    /// Renders the entries as keys and, for maps, values.
+    fn entries(&self) -> Vec<(String, Option<String>)>;
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> Collection for HashMap<K, V, S> {
+    fn count(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn entries(&self) -> Vec<(String, Option<String>)> {
+        map_entries(self)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> Collection for BTreeMap<K, V> {
+    fn count(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn entries(&self) -> Vec<(String, Option<String>)> {
+        map_entries(self)
+    }
+}
+
+impl<T: fmt::Debug, S> Collection for HashSet<T, S> {
+    fn count(&self) -> usize {
+        HashSet::len(self)
+    }
+
+    fn entries(&self) -> Vec<(String, Option<String>)> {
+        set_entries(self)
+    }
+}
+
+impl<T: fmt::Debug> Collection for BTreeSet<T> {
+    fn count(&self) -> usize {
+        BTreeSet::len(self)
+    }
+
+    fn entries(&self) -> Vec<(String, Option<String>)> {
+        set_entries(self)
+    }
+}
+
+impl<C: Collection + ?Sized> Collection for &C {
+    fn count(&self) -> usize {
+        C::count(self)
+    }
+
+    fn entries(&self) -> Vec<(String, Option<String>)> {
+        C::entries(self)
+    }
+}
+
+impl<C: Collection + ?Sized> Collection for &mut C {
+    fn count(&self) -> usize {
+        C::count(self)
+    }
+
+    fn entries(&self) -> Vec<(String, Option<String>)> {
+        C::entries(self)
+    }
+}
+
+fn map_entries<'a, K: fmt::Debug + 'a, V: fmt::Debug + 'a>(
+    map: impl IntoIterator<Item = (&'a K, &'a V)>,
+) -> Vec<(String, Option<String>)> {
+    map.into_iter()
+        .map(|(k, v)| (format!("{:?}", k), Some(format!("{:?}", v))))
+        .collect()
+}
+
+fn set_entries<'a, T: fmt::Debug + 'a>(
+    set: impl IntoIterator<Item = &'a T>,
+) -> Vec<(String, Option<String>)> {
+    set.into_iter()
+        .map(|t| (format!("{:?}", t), None))
+        .collect()
+}
+
+pub struct MapSummary<'a, C: ?Sized>(pub &'a C, pub bool);
+
+impl<C: Collection + ?Sized> fmt::Display for MapSummary<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let MapSummary(collection, stats) = *self;
+        let mut entries = collection.entries();
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        write!(f, "len {}: {{", collection.count())?;
+        for (i, (key, value)) in entries.iter().take(MAP_SAMPLES).enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(key)?;
+            if let Some(value) = value {
+                write!(f, ": {}", value)?;
+            }
+        }
+        if entries.len() > MAP_SAMPLES {
+            f.write_str(", …")?;
+        }
+        f.write_char('}')?;
+        if stats && !entries.is_empty() {
+            let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+            for (key, _) in &entries {
+                *kinds.entry(kind(key)).or_default() += 1;
+            }
+            let mut kinds: Vec<_> = kinds.into_iter().collect();
+            kinds.sort_by_key(|&(_, count)| Reverse(count));
+            f.write_str(" keys:")?;
+            for (i, (kind, count)) in kinds.iter().enumerate() {
+                let separator = if i > 0 { "," } else { "" };
+                write!(f, "{} {} {}", separator, kind, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Classifies a key by the start of its `Debug` output.
+fn kind(key: &str) -> &str {
+    match key.chars().next() {
+        Some('"') | Some('\'') => "string",
+        Some(c) if c.is_ascii_digit() || c == '-' => "number",
+        Some('(') => "tuple",
+        Some('[') => "list",
+        Some(c) if c.is_alphabetic() || c == '_' => {
+            let end = key
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+                .unwrap_or(key.len());
+            &key[..end]
+        }
+        _ => "other",
+    }
+}
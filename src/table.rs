@@ -0,0 +1,384 @@
+use std::fmt::{self, Write};
+use std::sync::{Mutex, PoisonError};
+
+use crate::sink;
+use crate::Callsite;
+
+/// Appends a row to a named table that is printed as a whole later on. This
+/// version of the macro will print nothing and be optimized out in release
+/// builds.
+///
+/// Every field becomes a column, named after its key and formatted with its
+/// `Debug` implementation. The rows of a table are kept in memory until the
+/// table is printed with [`flush_table`], or until the program exits or
+/// panics. This makes periodic measurements much easier to read than
+/// thousands of separate lines:
+///
+/// ```rust
+/// use dbgonly::dbg_row;
+/// for (t, fps) in [(0.0, 59.9), (0.5, 60.1), (1.0, 58.7)] {
+///     dbg_row!(table: "frames", time = t, fps = fps, ents = 1200);
+/// }
+/// dbgonly::flush_table("frames");
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:3] frames (3 rows)
+/// | time | fps  | ents |
+/// |  0.0 | 59.9 | 1200 |
+/// |  0.5 | 60.1 | 1200 |
+/// |  1.0 | 58.7 | 1200 |
+/// ```
+///
+/// The location printed is that of the first row. Rows with different keys
+/// can be mixed in one table; cells of columns a row does not have are left
+/// empty.
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_row {
+    (table: $table:expr, $($key:ident = $field:expr),+ $(,)?) => {{
+        static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+            file!(),
+            line!(),
+            module_path!(),
+            ::core::option::Option::None,
+        ).silenced_if($crate::__silenced!());
+        if CALLSITE.is_enabled() {
+            $crate::__private::add_row(
+                &CALLSITE,
+                &$table,
+                &[$((stringify!($key), &$field as &dyn ::core::fmt::Debug)),+]);
+        }
+    }};
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_row {
+    (table: $table:expr, $($key:ident = $field:expr),+ $(,)?) => {{
+        if false {
+            let _ = &$table;
+            $(let _ = &$field;)+
+        }
+    }};
+}
+
+/// Prints the items of a collection as an aligned table and returns the
+/// collection. This version of the macro will print nothing and be optimized
+/// out in release builds.
+///
+/// Each item gets a row, numbered in the first column, and each field of the
+/// items a column, which is much easier to scan than the nested output of
+/// [`dbgonly!`](crate::dbgonly) for a list of records:
+///
+/// ```rust
+/// use dbgonly::dbg_table;
+/// #[derive(Debug)]
+/// struct Entity { name: &'static str, hp: u32, pos: (f32, f32) }
+///
+/// let entities = vec![
+///     Entity { name: "player", hp: 100, pos: (0.0, 1.5) },
+///     Entity { name: "slime", hp: 12, pos: (4.0, -2.0) },
+/// ];
+/// let entities = dbg_table!(entities);
+/// # assert_eq!(entities.len(), 2);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:9] entities = 2 items
+/// | # | name     | hp  | pos         |
+/// | 0 | "player" | 100 | (0.0, 1.5)  |
+/// | 1 | "slime"  |  12 | (4.0, -2.0) |
+/// ```
+///
+/// The collection can be anything with an `iter` method, such as a `Vec`, a
+/// slice, an array or a map. The columns come from the `Debug` output of the
+/// items: named fields of structs, positions in tuples and tuple structs,
+/// and a single `value` column for anything else. If the items are of
+/// different types or enum variants, their names are shown in a `type`
+/// column.
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_table {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::items_table(tmp.iter())
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_table {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+}
+
+struct Table {
+    name: String,
+    callsite: &'static Callsite,
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<(usize, String)>>,
+}
+
+static TABLES: Mutex<Vec<Table>> = Mutex::new(Vec::new());
+
+pub fn add_row(
+    callsite: &'static Callsite,
+    table: &dyn fmt::Display,
+    fields: &[(&'static str, &dyn fmt::Debug)],
+) {
+    let name = table.to_string();
+    let mut tables = TABLES.lock().unwrap_or_else(PoisonError::into_inner);
+    let index = match tables.iter().position(|t| t.name == name) {
+        Some(index) => index,
+        None => {
+            tables.push(Table {
+                name,
+                callsite,
+                columns: Vec::new(),
+                rows: Vec::new(),
+            });
+            sink::flush::install_guards();
+            tables.len() - 1
+        }
+    };
+    let table = &mut tables[index];
+    let row = fields
+        .iter()
+        .map(|(key, value)| {
+            let column = match table.columns.iter().position(|c| c == key) {
+                Some(column) => column,
+                None => {
+                    table.columns.push(key);
+                    table.columns.len() - 1
+                }
+            };
+            (column, format!("{:?}", value))
+        })
+        .collect();
+    table.rows.push(row);
+}
+
+/// Prints the rows collected with [`dbg_row!`](crate::dbg_row) for the table
+/// `name` and empties it.
+///
+/// Nothing is printed if the table has no rows.
+pub fn flush_table(name: &str) {
+    let table = {
+        let mut tables = TABLES.lock().unwrap_or_else(PoisonError::into_inner);
+        match tables.iter().position(|t| t.name == name) {
+            Some(index) => tables.remove(index),
+            None => return,
+        }
+    };
+    print(&table);
+}
+
+/// Prints all tables that still have rows.
+pub(crate) fn flush_all() {
+    let tables = std::mem::take(&mut *TABLES.lock().unwrap_or_else(PoisonError::into_inner));
+    for table in &tables {
+        print(table);
+    }
+}
+
+fn print(table: &Table) {
+    let rows: Vec<Vec<&str>> = table
+        .rows
+        .iter()
+        .map(|row| {
+            let mut cells = vec![""; table.columns.len()];
+            for (column, cell) in row {
+                cells[*column] = cell;
+            }
+            cells
+        })
+        .collect();
+    let rendered = render(&table.columns, &rows);
+    let rows = match table.rows.len() {
+        1 => "1 row".to_owned(),
+        n => format!("{} rows", n),
+    };
+    crate::__private::emit(
+        table.callsite,
+        Some(format_args!("{} ({})\n{}", table.name, rows, rendered)),
+        &[],
+    );
+}
+
+/// Renders rows as an aligned table with a header line. Cells that look like
+/// numbers are aligned to the right.
+pub(crate) fn render(columns: &[&str], rows: &[Vec<&str>]) -> String {
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    let mut line = |cells: &mut dyn Iterator<Item = &str>| {
+        out.push('|');
+        for (cell, &width) in cells.zip(&widths) {
+            if cell.parse::<f64>().is_ok() {
+                let _ = write!(out, " {:>width$} |", cell, width = width);
+            } else {
+                let _ = write!(out, " {:<width$} |", cell, width = width);
+            }
+        }
+        out.push('\n');
+    };
+    line(&mut columns.iter().copied());
+    for row in rows {
+        line(&mut row.iter().copied());
+    }
+    out.pop();
+    out
+}
+
+/// Renders items as a table with a row per item and a column per field, led
+/// by the number of items.
+pub fn items_table(items: impl Iterator<Item = impl fmt::Debug>) -> String {
+    let debug: Vec<String> = items.map(|item| format!("{:?}", item)).collect();
+    let items: Vec<(&str, Vec<(String, &str)>)> = debug.iter().map(|d| fields(d)).collect();
+    let typed = items.iter().any(|(name, _)| *name != items[0].0);
+    let mut columns: Vec<String> = vec!["#".to_owned()];
+    if typed {
+        columns.push("type".to_owned());
+    }
+    let mut rows: Vec<Vec<String>> = Vec::with_capacity(items.len());
+    for (index, (name, fields)) in items.iter().enumerate() {
+        let mut row = vec![index.to_string()];
+        if typed {
+            row.push(name.to_string());
+        }
+        for (key, value) in fields {
+            // Unit variants are already named in the type column.
+            if typed && value == name {
+                continue;
+            }
+            let column = match columns.iter().position(|c| c == key) {
+                Some(column) => column,
+                None => {
+                    columns.push(key.clone());
+                    columns.len() - 1
+                }
+            };
+            if row.len() <= column {
+                row.resize(column + 1, String::new());
+            }
+            row[column] = value.to_string();
+        }
+        rows.push(row);
+    }
+    let rows: Vec<Vec<&str>> = rows
+        .iter()
+        .map(|row| {
+            let mut cells: Vec<&str> = row.iter().map(String::as_str).collect();
+            cells.resize(columns.len(), "");
+            cells
+        })
+        .collect();
+    let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let count = match rows.len() {
+        1 => "1 item".to_owned(),
+        n => format!("{} items", n),
+    };
+    if rows.is_empty() {
+        count
+    } else {
+        format!("{}\n{}", count, render(&columns, &rows))
+    }
+}
+
+/// Splits the single-line `Debug` output of a value into its type name and
+/// its fields.
+fn fields(debug: &str) -> (&str, Vec<(String, &str)>) {
+    let is_name = |name: &str| {
+        name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == ':')
+    };
+    if let Some((name, body)) = debug
+        .strip_suffix('}')
+        .and_then(|rest| rest.split_once(" {"))
+        .filter(|(name, _)| !name.is_empty() && is_name(name))
+    {
+        let fields = split_top_level(body)
+            .into_iter()
+            .filter_map(|field| field.split_once(": "))
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect::<Vec<_>>();
+        if !fields.is_empty() {
+            return (name, fields);
+        }
+    }
+    if let Some((name, body)) = debug
+        .strip_suffix(')')
+        .and_then(|rest| rest.split_once('('))
+        .filter(|(name, _)| name.is_empty() || is_name(name))
+    {
+        let fields = split_top_level(body)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| (i.to_string(), value))
+            .collect::<Vec<_>>();
+        if !fields.is_empty() {
+            return (name, fields);
+        }
+    }
+    let name = if is_name(debug) { debug } else { "" };
+    (name, vec![("value".to_owned(), debug)])
+}
+
+/// Splits at the commas that are not nested in brackets or literals.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    parts.push(s[start..i].trim());
+                    start = i + 1;
+                }
+                _ => {}
+            },
+        }
+    }
+    parts.push(s[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
@@ -0,0 +1,73 @@
+use std::env;
+use std::sync::OnceLock;
+
+use crate::sink;
+
+/// What the terminal that records are printed to supports.
+///
+/// Escape sequences are only worth writing when a person is watching stderr
+/// in a terminal; in a file or a pipe they are noise. Every feature of the
+/// output that relies on them is switched off automatically when stderr is
+/// not a terminal or a [`Sink`](crate::Sink) is installed, so logs of piped
+/// runs stay plain text without any configuration:
+///
+/// ```rust
+/// let terminal = dbgonly::terminal();
+/// if !terminal.color {
+///     // Output is redirected, `NO_COLOR` is set or the `tty` feature is off.
+/// }
+/// ```
+///
+/// Detection requires the `tty` feature, which is enabled by default.
+/// Without it, stderr is never treated as a terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Terminal {
+    /// Whether ANSI colors are used.
+    ///
+    /// This is the case on a terminal unless `NO_COLOR` is set to a
+    /// non-empty value or `TERM` is `dumb`. Setting `CLICOLOR_FORCE` to a
+    /// value other than `0` enables colors even when stderr is not a
+    /// terminal, and `NO_COLOR` takes precedence over it.
+    pub color: bool,
+}
+
+/// Returns what the terminal that records are printed to supports.
+///
+/// The environment and stderr are inspected once, on the first call. See
+/// [`Terminal`].
+pub fn terminal() -> Terminal {
+    if sink::writes_to_stderr() {
+        detected()
+    } else {
+        Terminal::default()
+    }
+}
+
+/// Returns what stderr supports, whether or not records are printed to it.
+///
+/// Unlike [`terminal`], this does not look at the installed sink, so it can
+/// be called by a sink while the sink is in use.
+pub(crate) fn detected() -> Terminal {
+    static DETECTED: OnceLock<Terminal> = OnceLock::new();
+    *DETECTED.get_or_init(detect)
+}
+
+fn detect() -> Terminal {
+    let tty = is_tty() && env::var_os("TERM").is_none_or(|term| term != "dumb");
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let force_color = env::var_os("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0");
+    Terminal {
+        color: !no_color && (tty || force_color),
+    }
+}
+
+#[cfg(feature = "tty")]
+fn is_tty() -> bool {
+    std::io::IsTerminal::is_terminal(&std::io::stderr())
+}
+
+#[cfg(not(feature = "tty"))]
+fn is_tty() -> bool {
+    false
+}
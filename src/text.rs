@@ -0,0 +1,332 @@
+use std::fmt::{self, Write};
+
+use crate::{diff, table, term};
+
+/// Prints a string with its invisible characters made visible and returns
+/// it. This version of the macro will print nothing and be optimized out in
+/// release builds.
+///
+/// Spaces, tabs, line breaks and other characters that are hard to see or
+/// tell apart are replaced with markers, and the length in bytes and chars is
+/// printed along with the string. This is what is needed when two strings
+/// look identical but do not compare equal:
+///
+/// ```rust
+/// use dbgonly::dbg_str;
+/// let name = dbg_str!("Zoë\u{200b} \t\r\n");
+/// //         ^-- prints: [src/main.rs:2] "Zoë\u{200b} \t\r\n" = "Zoë<U+200B>·→␍↵" (11 bytes, 8 chars)
+/// # assert_eq!(name.len(), 11);
+/// ```
+///
+/// | Character                                      | Marker     |
+/// |------------------------------------------------|------------|
+/// | space                                          | `·`        |
+/// | tab                                            | `→`        |
+/// | line feed                                      | `↵`        |
+/// | carriage return                                | `␍`        |
+/// | other control, zero-width and space characters | `<U+XXXX>` |
+///
+/// The expression can be anything that implements `AsRef<str>`.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_str {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::Visible(::core::convert::AsRef::<str>::as_ref(&tmp))
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_str!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_str {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_str!($val)),+,)
+    };
+}
+
+pub struct Visible<'a>(pub &'a str);
+
+impl fmt::Display for Visible<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                ' ' => f.write_char('·')?,
+                '\t' => f.write_char('→')?,
+                '\n' => f.write_char('↵')?,
+                '\r' => f.write_char('␍')?,
+                c if is_invisible(c) => write!(f, "<U+{:04X}>", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        write!(
+            f,
+            "\" ({} bytes, {} chars)",
+            self.0.len(),
+            self.0.chars().count()
+        )
+    }
+}
+
+/// Returns whether a character is invisible or easily mistaken for a plain
+/// space when printed.
+fn is_invisible(c: char) -> bool {
+    c.is_control()
+        || c.is_whitespace()
+        || matches!(
+            c,
+            '\u{00AD}'
+                | '\u{034F}'
+                | '\u{061C}'
+                | '\u{115F}'
+                | '\u{1160}'
+                | '\u{17B4}'
+                | '\u{17B5}'
+                | '\u{180E}'
+                | '\u{200B}'..='\u{200F}'
+                | '\u{202A}'..='\u{202E}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{206F}'
+                | '\u{3164}'
+                | '\u{FE00}'..='\u{FE0F}'
+                | '\u{FEFF}'
+                | '\u{FFA0}'
+                | '\u{FFF9}'..='\u{FFFB}'
+        )
+}
+
+/// Prints a unified diff of two multi-line strings and returns them as a
+/// tuple. This version of the macro will print nothing and be optimized out
+/// in release builds.
+///
+/// The diff is line-based, with three lines of context around each change,
+/// and is colored when the output goes straight to a terminal. Both
+/// expressions can be anything that implements `AsRef<str>`:
+///
+/// ```rust
+/// use dbgonly::dbg_diff_str;
+/// let expected = "fn main() {\n    println!(\"hi\");\n}\n";
+/// let actual = "fn main() {\n    println!(\"hello\");\n}\n";
+/// dbg_diff_str!(expected, actual);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:4] expected vs actual = 1 hunk, +1 -1
+/// @@ -1,3 +1,3 @@
+///  fn main() {
+/// -    println!("hi");
+/// +    println!("hello");
+///  }
+/// ```
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_diff_str {
+    ($old:expr, $new:expr $(,)?) => {
+        match ($old, $new) {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(concat!(
+                        stringify!($old),
+                        " vs ",
+                        stringify!($new)
+                    )),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::unified_diff(
+                            ::core::convert::AsRef::<str>::as_ref(&tmp.0),
+                            ::core::convert::AsRef::<str>::as_ref(&tmp.1),
+                        )
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_diff_str {
+    ($old:expr, $new:expr $(,)?) => {
+        ($old, $new)
+    };
+}
+
+pub fn unified_diff(old: &str, new: &str) -> String {
+    diff::unified(old, new, term::terminal().color)
+}
+
+/// Prints every char of a string with its code point, UTF-8 length and
+/// category and returns the string. This version of the macro will print
+/// nothing and be optimized out in release builds.
+///
+/// This makes normalization and encoding problems visible, such as a letter
+/// followed by a combining accent where a precomposed letter was expected:
+///
+/// ```rust
+/// use dbgonly::dbg_chars;
+/// let name = dbg_chars!("Zoe\u{301}");
+/// # assert_eq!(name.len(), 5);
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [src/main.rs:2] "Zoe\u{301}" = 4 chars, 5 bytes
+/// | byte | char | code point | len | category         |
+/// |    0 | Z    | U+005A     |   1 | uppercase letter |
+/// |    1 | o    | U+006F     |   1 | lowercase letter |
+/// |    2 | e    | U+0065     |   1 | lowercase letter |
+/// |    3 | ◌́   | U+0301     |   2 | combining mark   |
+/// ```
+///
+/// Categories are derived from the standard library's character
+/// classification, so they are coarser than the Unicode general categories.
+/// Well-known invisible characters such as `ZERO WIDTH SPACE` are also named.
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_chars {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::__emit!(
+                    ::core::option::Option::Some(stringify!($val)),
+                    ::core::option::Option::Some(format_args!(
+                        "{}",
+                        $crate::__private::chars_table(::core::convert::AsRef::<str>::as_ref(&tmp))
+                    )),
+                    &[]
+                );
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_chars {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+}
+
+pub fn chars_table(s: &str) -> String {
+    let rows: Vec<[String; 5]> = s
+        .char_indices()
+        .map(|(i, c)| {
+            let shown = match c {
+                c if is_combining(c) => format!("◌{}", c),
+                c if c.is_control() || is_invisible(c) => String::new(),
+                c => c.to_string(),
+            };
+            let category = match name(c) {
+                Some(name) => format!("{} ({})", category(c), name),
+                None => category(c).to_owned(),
+            };
+            [
+                i.to_string(),
+                shown,
+                format!("U+{:04X}", c as u32),
+                c.len_utf8().to_string(),
+                category,
+            ]
+        })
+        .collect();
+    let rows: Vec<Vec<&str>> = rows
+        .iter()
+        .map(|row| row.iter().map(String::as_str).collect())
+        .collect();
+    format!(
+        "{} chars, {} bytes\n{}",
+        s.chars().count(),
+        s.len(),
+        table::render(&["byte", "char", "code point", "len", "category"], &rows)
+    )
+}
+
+fn category(c: char) -> &'static str {
+    match c {
+        c if c.is_control() => "control",
+        c if c.is_whitespace() => "whitespace",
+        c if is_combining(c) => "combining mark",
+        c if is_invisible(c) => "format",
+        c if c.is_uppercase() => "uppercase letter",
+        c if c.is_lowercase() => "lowercase letter",
+        c if c.is_alphabetic() => "letter",
+        c if c.is_ascii_digit() => "digit",
+        c if c.is_numeric() => "numeric",
+        c if c.is_ascii_punctuation() => "punctuation",
+        _ => "other",
+    }
+}
+
+fn is_combining(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+/// Names the characters that are most often behind confusing strings.
+fn name(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\0' => "NULL",
+        '\t' => "CHARACTER TABULATION",
+        '\n' => "LINE FEED",
+        '\r' => "CARRIAGE RETURN",
+        '\u{1B}' => "ESCAPE",
+        ' ' => "SPACE",
+        '\u{7F}' => "DELETE",
+        '\u{A0}' => "NO-BREAK SPACE",
+        '\u{AD}' => "SOFT HYPHEN",
+        '\u{2002}' => "EN SPACE",
+        '\u{2003}' => "EM SPACE",
+        '\u{2009}' => "THIN SPACE",
+        '\u{200B}' => "ZERO WIDTH SPACE",
+        '\u{200C}' => "ZERO WIDTH NON-JOINER",
+        '\u{200D}' => "ZERO WIDTH JOINER",
+        '\u{200E}' => "LEFT-TO-RIGHT MARK",
+        '\u{200F}' => "RIGHT-TO-LEFT MARK",
+        '\u{2028}' => "LINE SEPARATOR",
+        '\u{2029}' => "PARAGRAPH SEPARATOR",
+        '\u{202F}' => "NARROW NO-BREAK SPACE",
+        '\u{2060}' => "WORD JOINER",
+        '\u{3000}' => "IDEOGRAPHIC SPACE",
+        '\u{FEFF}' => "ZERO WIDTH NO-BREAK SPACE",
+        '\u{FFFD}' => "REPLACEMENT CHARACTER",
+        _ => return None,
+    })
+}
@@ -0,0 +1,122 @@
+use std::io::{self, Write};
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+use crate::{config, Callsite};
+
+struct Event {
+    at: Instant,
+    label: String,
+    thread: Option<String>,
+    file: &'static str,
+    line: u32,
+}
+
+static TIMELINE: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+/// Records a timestamped event in the global timeline. This version of the
+/// macro will record nothing and be optimized out in release builds.
+///
+/// Nothing is printed when the event is recorded. The whole timeline is
+/// printed in order, with the time that passed between consecutive events,
+/// by [`dump_timeline`](crate::dump_timeline) and
+/// [`dump_state`](crate::dump_state). The label can be a format string with
+/// arguments:
+///
+/// ```rust
+/// use dbgonly::dbg_event;
+/// dbg_event!("socket connected");
+/// for attempt in 1..=2 {
+///     dbg_event!("handshake attempt {}", attempt);
+/// }
+/// dbgonly::dump_timeline();
+/// ```
+///
+/// This prints to [stderr]:
+///
+/// ```text,ignore
+/// [dbgonly] timeline (3 events):
+///        +0ns [src/main.rs:2] socket connected (main)
+///   +15.204µs [src/main.rs:4] handshake attempt 1 (main)
+///    +1.872µs [src/main.rs:4] handshake attempt 2 (main)
+/// ```
+///
+/// [stderr]: https://en.wikipedia.org/wiki/Standard_streams#Standard_error_(stderr)
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_event {
+    ($($arg:tt)+) => {{
+        static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+            file!(),
+            line!(),
+            module_path!(),
+            ::core::option::Option::None,
+        ).silenced_if($crate::__silenced!());
+        if CALLSITE.is_enabled() {
+            $crate::__private::record_event(&CALLSITE, format_args!($($arg)+));
+        }
+    }};
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_event {
+    ($($arg:tt)+) => {{
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+pub fn record_event(callsite: &'static Callsite, label: std::fmt::Arguments<'_>) {
+    let event = Event {
+        at: Instant::now(),
+        label: label.to_string(),
+        thread: std::thread::current().name().map(str::to_owned),
+        file: callsite.file(),
+        line: callsite.line(),
+    };
+    TIMELINE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(event);
+}
+
+/// Prints the events recorded with [`dbg_event!`](crate::dbg_event) to
+/// stderr, oldest first.
+///
+/// Each event is preceded by the time that passed since the previous one. In
+/// [deterministic](crate::set_deterministic) mode, the times are printed as
+/// `ELAPSED`.
+pub fn dump_timeline() {
+    let mut stderr = io::stderr().lock();
+    let _ = write_timeline(&mut stderr);
+    let _ = stderr.flush();
+}
+
+pub(crate) fn write_timeline(w: &mut dyn Write) -> io::Result<()> {
+    let timeline = TIMELINE.lock().unwrap_or_else(PoisonError::into_inner);
+    if timeline.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "[dbgonly] timeline ({} events):", timeline.len())?;
+    let mut previous = timeline[0].at;
+    for event in timeline.iter() {
+        let delta = if config::deterministic() {
+            "+ELAPSED".to_owned()
+        } else {
+            format!("+{:?}", event.at.duration_since(previous))
+        };
+        write!(
+            w,
+            "{:>12} [{}:{}] {}",
+            delta, event.file, event.line, event.label
+        )?;
+        match &event.thread {
+            Some(thread) => writeln!(w, " ({})", thread)?,
+            None => writeln!(w)?,
+        }
+        previous = event.at;
+    }
+    Ok(())
+}
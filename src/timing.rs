@@ -0,0 +1,86 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::{config, context, sink, Callsite, Record};
+
+/// Evaluates an expression and prints how long it took along with its value,
+/// and returns the value. This version of the macro will print nothing, take
+/// no time and be optimized out in release builds.
+///
+/// This is quick and dirty profiling with the same ergonomics as
+/// [`dbgonly!`](crate::dbgonly):
+///
+/// ```rust
+/// use dbgonly::dbg_elapsed;
+/// let primes = dbg_elapsed!((2..10_000u32).filter(|n| (2..*n).all(|d| n % d != 0)).count());
+/// //           ^-- prints: [src/main.rs:2] (2..10_000u32).filter(|n| (2..*n).all(|d| n % d != 0)).count() took 41.2ms = 1229
+/// # assert_eq!(primes, 1229);
+/// ```
+///
+/// The time only covers the evaluation of the expression, not the printing.
+/// In [deterministic](crate::set_deterministic) mode, it is printed as
+/// `ELAPSED`.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_elapsed {
+    ($val:expr $(,)?) => {{
+        static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+            file!(),
+            line!(),
+            module_path!(),
+            ::core::option::Option::Some(stringify!($val)),
+        ).silenced_if($crate::__silenced!());
+        match $crate::__private::Instant::now() {
+            start => match $val {
+                tmp => {
+                    let elapsed = start.elapsed();
+                    if CALLSITE.is_enabled() {
+                        $crate::__private::emit_elapsed(
+                            &CALLSITE,
+                            elapsed,
+                            format_args!("{:#?}", &tmp),
+                        );
+                    }
+                    tmp
+                }
+            },
+        }
+    }};
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_elapsed!($val)),+,)
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_elapsed {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => tmp,
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_elapsed!($val)),+,)
+    };
+}
+
+#[cold]
+#[inline(never)]
+pub fn emit_elapsed(callsite: &'static Callsite, elapsed: Duration, value: fmt::Arguments<'_>) {
+    let expr = callsite.expr().unwrap_or_default();
+    let expr = if config::deterministic() {
+        format!("{} took ELAPSED", expr)
+    } else {
+        format!("{} took {:?}", expr, elapsed)
+    };
+    let context = context::current();
+    sink::emit(&Record::new(
+        callsite.file(),
+        callsite.line(),
+        callsite.module_path(),
+        Some(&expr),
+        Some(value),
+        &[],
+        context.as_deref(),
+    ));
+}
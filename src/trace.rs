@@ -0,0 +1,76 @@
+use std::fmt;
+
+use crate::{group, Callsite};
+
+// Expands to the traced function generated by `#[trace]` in debug builds and
+// to the function as written otherwise, so that the choice follows the same
+// configuration as the other macros.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! __trace {
+    ({ $($traced:tt)* } { $($plain:tt)* }) => {
+        $($traced)*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! __trace {
+    ({ $($traced:tt)* } { $($plain:tt)* }) => {
+        $($plain)*
+    };
+}
+
+/// A call of a function annotated with `#[trace]`, which prints the entry
+/// when created and the exit with [`exit`](Trace::exit).
+pub struct Trace {
+    exit: &'static Callsite,
+    /// The depth to restore on exit, if the records in between are indented.
+    depth: Option<usize>,
+}
+
+impl Trace {
+    /// Prints the entry with the arguments as fields. Async functions may
+    /// resume on other threads, so their calls are not indented.
+    pub fn enter(
+        enter: &'static Callsite,
+        exit: &'static Callsite,
+        args: &[(&'static str, &dyn fmt::Debug)],
+        indent: bool,
+    ) -> Self {
+        if enter.is_enabled() {
+            crate::__private::emit(enter, None, args);
+        }
+        Trace {
+            exit,
+            depth: indent.then(group::indent),
+        }
+    }
+
+    /// Prints the exit with the return value, or without one for functions
+    /// that return `()`.
+    pub fn exit(mut self, value: Option<&dyn fmt::Debug>) {
+        if let Some(depth) = self.depth.take() {
+            group::restore_depth(depth);
+        }
+        if self.exit.is_enabled() {
+            match value {
+                Some(value) => {
+                    crate::__private::emit(self.exit, Some(format_args!("{:#?}", value)), &[])
+                }
+                None => crate::__private::emit(self.exit, None, &[]),
+            }
+        }
+    }
+}
+
+impl Drop for Trace {
+    // Unwinding skips `exit`.
+    fn drop(&mut self) {
+        if let Some(depth) = self.depth {
+            group::restore_depth(depth);
+        }
+    }
+}
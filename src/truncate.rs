@@ -0,0 +1,94 @@
+use std::fmt::{self, Write};
+
+use crate::config::MaxOutput;
+
+/// Formats a value, keeping at most as much of it as the limits allow and
+/// noting how much was cut off.
+///
+/// The rest of the value is still formatted to count it, but not kept, so a
+/// huge value costs time but not memory.
+pub(crate) fn render(value: fmt::Arguments<'_>, max: MaxOutput) -> String {
+    let mut limited = Limited {
+        out: String::new(),
+        lines: 1,
+        max_lines: max.lines.unwrap_or(usize::MAX).max(1),
+        max_bytes: max.bytes.unwrap_or(usize::MAX),
+        cut: None,
+        more_newlines: 0,
+        more_bytes: 0,
+    };
+    let _ = limited.write_fmt(value);
+    match limited.cut {
+        None => {}
+        Some(Cut::Lines) => {
+            let more = limited.more_newlines + 1;
+            let _ = write!(
+                limited.out,
+                "\n... (truncated, {} more line{})",
+                more,
+                if more == 1 { "" } else { "s" }
+            );
+        }
+        Some(Cut::Bytes) => {
+            let more = limited.more_bytes;
+            let _ = write!(
+                limited.out,
+                "... (truncated, {} more byte{})",
+                more,
+                if more == 1 { "" } else { "s" }
+            );
+        }
+    }
+    limited.out
+}
+
+enum Cut {
+    Lines,
+    Bytes,
+}
+
+struct Limited {
+    out: String,
+    /// The number of the line being written.
+    lines: usize,
+    max_lines: usize,
+    max_bytes: usize,
+    cut: Option<Cut>,
+    /// The newlines and bytes after the cut.
+    more_newlines: usize,
+    more_bytes: usize,
+}
+
+impl fmt::Write for Limited {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.cut.is_some() {
+            self.more_newlines += s.matches('\n').count();
+            self.more_bytes += s.len();
+            return Ok(());
+        }
+        for (i, c) in s.char_indices() {
+            if c == '\n' && self.lines == self.max_lines {
+                // The newline is replaced by the one before the note.
+                self.cut(Cut::Lines, &s[i + 1..]);
+                return Ok(());
+            }
+            if self.out.len() + c.len_utf8() > self.max_bytes {
+                self.cut(Cut::Bytes, &s[i..]);
+                return Ok(());
+            }
+            if c == '\n' {
+                self.lines += 1;
+            }
+            self.out.push(c);
+        }
+        Ok(())
+    }
+}
+
+impl Limited {
+    fn cut(&mut self, cut: Cut, rest: &str) {
+        self.cut = Some(cut);
+        self.more_newlines += rest.matches('\n').count();
+        self.more_bytes += rest.len();
+    }
+}
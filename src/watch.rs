@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Mutex, PoisonError};
+
+use crate::Callsite;
+
+struct Watch {
+    value: String,
+    file: &'static str,
+    line: u32,
+}
+
+static WATCHES: Mutex<BTreeMap<String, Watch>> = Mutex::new(BTreeMap::new());
+
+/// Stores the value of a given expression under a name and returns the
+/// value. This version of the macro will store nothing and be optimized out
+/// in release builds.
+///
+/// Nothing is printed when the value is stored. Instead, the latest value
+/// stored under each name can be printed later from anywhere in the program
+/// with [`dbg_dump!`](crate::dbg_dump) or [`dump_all`](crate::dump_all), for
+/// example from a panic hook:
+///
+/// ```rust
+/// use dbgonly::{dbg_dump, dbg_set};
+/// fn receive(packet: &[u8]) {
+///     dbg_set!("last_packet", packet);
+/// }
+///
+/// receive(&[1, 2, 3]);
+/// dbg_dump!("last_packet");
+/// //  ^-- prints: [src/main.rs:7] last_packet = [1, 2, 3]
+/// ```
+///
+/// The value is rendered with its `Debug` implementation when it is stored.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_set {
+    ($name:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                static CALLSITE: $crate::Callsite = $crate::Callsite::new(
+                    file!(),
+                    line!(),
+                    module_path!(),
+                    ::core::option::Option::Some(stringify!($val)),
+                )
+                .silenced_if($crate::__silenced!());
+                if CALLSITE.is_enabled() {
+                    $crate::__private::set_watch(&CALLSITE, &$name, format_args!("{:#?}", &tmp));
+                }
+                tmp
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_set {
+    ($name:expr, $val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                if false {
+                    let _ = &$name;
+                }
+                tmp
+            }
+        }
+    };
+}
+
+/// Prints the value last stored under a name with
+/// [`dbg_set!`](crate::dbg_set). This version of the macro will print nothing
+/// and be optimized out in release builds.
+///
+/// The location printed is that of the `dbg_dump!` invocation. Names for
+/// which no value has been stored are printed as `<unset>`.
+#[macro_export]
+#[cfg(dbgonly_active)]
+macro_rules! dbg_dump {
+    ($name:expr $(,)?) => {
+        $crate::__private::dump_watch(&$name, |name, value| {
+            $crate::__emit!(
+                ::core::option::Option::None,
+                ::core::option::Option::Some(format_args!("{} = {}", name, value)),
+                &[]
+            )
+        })
+    };
+}
+
+#[macro_export]
+#[cfg(not(dbgonly_active))]
+macro_rules! dbg_dump {
+    ($name:expr $(,)?) => {{
+        if false {
+            let _ = &$name;
+        }
+    }};
+}
+
+pub fn set_watch(callsite: &'static Callsite, name: &dyn fmt::Display, value: fmt::Arguments<'_>) {
+    let watch = Watch {
+        value: value.to_string(),
+        file: callsite.file(),
+        line: callsite.line(),
+    };
+    WATCHES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(name.to_string(), watch);
+}
+
+pub fn dump_watch(name: &dyn fmt::Display, emit: impl FnOnce(&str, &str)) {
+    let name = name.to_string();
+    let value = WATCHES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&name)
+        .map(|watch| watch.value.clone());
+    emit(&name, value.as_deref().unwrap_or("<unset>"));
+}
+
+/// Prints the latest value stored under every name with
+/// [`dbg_set!`](crate::dbg_set) to stderr, sorted by name.
+///
+/// This is also part of [`dump_state`](crate::dump_state). Like it, it can be
+/// called from anywhere, including a panic hook:
+///
+/// ```rust
+/// std::panic::set_hook(Box::new(|info| {
+///     dbgonly::dump_all();
+///     eprintln!("{}", info);
+/// }));
+/// # let _ = std::panic::take_hook();
+/// ```
+pub fn dump_all() {
+    let mut stderr = io::stderr().lock();
+    let _ = write_all(&mut stderr);
+    let _ = stderr.flush();
+}
+
+pub(crate) fn write_all(w: &mut dyn Write) -> io::Result<()> {
+    let watches = WATCHES.lock().unwrap_or_else(PoisonError::into_inner);
+    if watches.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "[dbgonly] watches ({}):", watches.len())?;
+    for (name, watch) in watches.iter() {
+        writeln!(
+            w,
+            "[{}:{}] {} = {}",
+            watch.file, watch.line, name, watch.value
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! A `Baseline` recorded in one run is compared against in the next, so the
+//! values have to survive the round trip through the file unchanged. Each
+//! run is a child process, since the report is written straight to stderr.
+
+#![cfg(feature = "std")]
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::process::Command;
+
+use dbgonly::sink::{self, Baseline};
+
+const CHILD: &str = "DBGONLY_TEST_BASELINE";
+
+/// Prints its text exactly, line breaks included.
+struct Raw(&'static str);
+
+impl fmt::Debug for Raw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[test]
+#[cfg(not(feature = "force-disable"))]
+fn values_with_line_breaks_round_trip() {
+    if let Some(path) = env::var_os(CHILD) {
+        sink::set_sink(Baseline::open(path).unwrap());
+        for text in ["trailing\n", "crlf\r\nline\r\n", "\n\n", ""] {
+            dbgonly::dbgtest!(Raw(text));
+        }
+        sink::flush();
+        return;
+    }
+
+    let path = env::temp_dir().join(format!("dbgonly-baseline-{}.dbg", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let run = || {
+        let output = Command::new(env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "values_with_line_breaks_round_trip",
+                "--nocapture",
+            ])
+            .env(CHILD, &path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stderr).unwrap()
+    };
+    run();
+    let report = run();
+    let _ = fs::remove_file(&path);
+    assert!(
+        report.contains("[dbgonly] all 1 call sites match"),
+        "{}",
+        report
+    );
+}
@@ -0,0 +1,22 @@
+//! `dbgtest!` prints in the tests of the invoking crate, which include
+//! integration tests like these, in debug and release profiles alike, unless
+//! the `force-disable` feature is enabled.
+
+#![cfg(feature = "std")]
+
+#[test]
+#[cfg(not(feature = "force-disable"))]
+fn prints_in_tests() {
+    let capture = dbgonly::capture();
+    assert_eq!(dbgonly::dbgtest!(1 + 1), 2);
+    let output = capture.output();
+    assert!(output.contains("1 + 1 = 2"), "{:?}", output);
+}
+
+#[test]
+#[cfg(feature = "force-disable")]
+fn silenced_by_force_disable() {
+    let capture = dbgonly::capture();
+    assert_eq!(dbgonly::dbgtest!(1 + 1), 2);
+    assert_eq!(capture.output(), "");
+}